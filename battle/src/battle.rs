@@ -1,11 +1,11 @@
 use std::collections::VecDeque;
 
-use libtetris::Controller;
+use libtetris::{Controller, FinesseFault};
 use rand::prelude::*;
 use rand_pcg::Pcg64Mcg;
 use serde::{Deserialize, Serialize};
 
-use crate::{Event, Game, GameConfig};
+use crate::{Event, FinesseTracker, Game, GameConfig};
 
 pub struct Battle {
     pub player_1: Game,
@@ -111,3 +111,72 @@ pub struct Replay {
     pub p2_config: GameConfig,
     pub updates: VecDeque<(Controller, Controller)>,
 }
+
+impl Replay {
+    /// Replays the battle, checking each player's placements for finesse faults.
+    pub fn finesse_faults(&self) -> (Vec<FinesseFault>, Vec<FinesseFault>) {
+        let mut battle = Battle::new(
+            self.p1_config,
+            self.p2_config,
+            self.p1_seed,
+            self.p2_seed,
+            self.garbage_seed,
+        );
+        let mut p1_tracker = FinesseTracker::new(&self.p1_config);
+        let mut p2_tracker = FinesseTracker::new(&self.p2_config);
+        let mut p1_faults = vec![];
+        let mut p2_faults = vec![];
+        for &(p1, p2) in &self.updates {
+            let update = battle.update(p1, p2);
+            p1_faults.extend(p1_tracker.update(
+                &battle.player_1.board,
+                p1,
+                &update.player_1.events,
+            ));
+            p2_faults.extend(p2_tracker.update(
+                &battle.player_2.board,
+                p2,
+                &update.player_2.events,
+            ));
+        }
+        (p1_faults, p2_faults)
+    }
+}
+
+#[cfg(feature = "fumen")]
+impl Replay {
+    /// Replays the battle, encoding each player's placements as a fumen with one page per piece.
+    pub fn to_fumen(&self) -> (libtetris::fumen::Fumen, libtetris::fumen::Fumen) {
+        let mut battle = Battle::new(
+            self.p1_config,
+            self.p2_config,
+            self.p1_seed,
+            self.p2_seed,
+            self.garbage_seed,
+        );
+        let mut p1_fumen = libtetris::fumen::Fumen::default();
+        let mut p2_fumen = libtetris::fumen::Fumen::default();
+        for &(p1, p2) in &self.updates {
+            // pages show the board before the piece was placed
+            let p1_board = battle.player_1.board.clone();
+            let p2_board = battle.player_2.board.clone();
+            let update = battle.update(p1, p2);
+            add_placement_pages(&mut p1_fumen, &p1_board, &update.player_1.events);
+            add_placement_pages(&mut p2_fumen, &p2_board, &update.player_2.events);
+        }
+        (p1_fumen, p2_fumen)
+    }
+}
+
+#[cfg(feature = "fumen")]
+fn add_placement_pages(
+    fumen: &mut libtetris::fumen::Fumen,
+    board: &libtetris::Board<libtetris::ColoredRow>,
+    events: &[Event],
+) {
+    for event in events {
+        if let Event::PiecePlaced { piece, locked, .. } = event {
+            board.add_fumen_page(fumen, Some(*piece), locked.fumen_comment());
+        }
+    }
+}
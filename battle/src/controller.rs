@@ -50,6 +50,7 @@ impl PieceMoveExecutor {
                                     self.controller.right = false;
                                     self.controller.rotate_left = false;
                                     self.controller.rotate_right = false;
+                                    self.controller.rotate_180 = false;
                                     self.controller.left = false;
 
                                     self.controller.soft_drop = true;
@@ -62,6 +63,7 @@ impl PieceMoveExecutor {
                                     self.controller.right = false;
                                     self.controller.rotate_left = false;
                                     self.controller.rotate_right = false;
+                                    self.controller.rotate_180 = false;
                                     self.controller.soft_drop = false;
 
                                     self.controller.left ^= true;
@@ -73,6 +75,7 @@ impl PieceMoveExecutor {
                                     self.controller.left = false;
                                     self.controller.rotate_left = false;
                                     self.controller.rotate_right = false;
+                                    self.controller.rotate_180 = false;
                                     self.controller.soft_drop = false;
 
                                     self.controller.right ^= true;
@@ -84,6 +87,7 @@ impl PieceMoveExecutor {
                                     self.controller.right = false;
                                     self.controller.rotate_left = false;
                                     self.controller.left = false;
+                                    self.controller.rotate_180 = false;
                                     self.controller.soft_drop = false;
 
                                     self.controller.rotate_right ^= true;
@@ -95,6 +99,7 @@ impl PieceMoveExecutor {
                                     self.controller.left = false;
                                     self.controller.right = false;
                                     self.controller.rotate_right = false;
+                                    self.controller.rotate_180 = false;
                                     self.controller.soft_drop = false;
 
                                     self.controller.rotate_left ^= true;
@@ -102,6 +107,18 @@ impl PieceMoveExecutor {
                                         self.executing.pop_front();
                                     }
                                 }
+                                Some(PieceMovement::Flip) => {
+                                    self.controller.left = false;
+                                    self.controller.right = false;
+                                    self.controller.rotate_left = false;
+                                    self.controller.rotate_right = false;
+                                    self.controller.soft_drop = false;
+
+                                    self.controller.rotate_180 ^= true;
+                                    if self.controller.rotate_180 {
+                                        self.executing.pop_front();
+                                    }
+                                }
                             }
                         }
                         self.input_timer = self.speed_limit;
@@ -0,0 +1,49 @@
+use libtetris::{Board, ColoredRow, Controller, FinesseChecker, FinesseFault};
+
+use crate::{Event, GameConfig};
+
+/// Checks a player's finesse using their controller and game events each frame.
+pub struct FinesseTracker {
+    checker: FinesseChecker,
+    falling: bool,
+}
+
+impl FinesseTracker {
+    pub fn new(config: &GameConfig) -> Self {
+        FinesseTracker {
+            checker: FinesseChecker::new(config.rotation_system, config.flip_kicks),
+            falling: false,
+        }
+    }
+
+    /// Returns the finesse fault of the piece placed this frame, if there was one.
+    pub fn update(
+        &mut self,
+        board: &Board<ColoredRow>,
+        controller: Controller,
+        events: &[Event],
+    ) -> Option<FinesseFault> {
+        self.checker.update(controller);
+        let mut fault = None;
+        for event in events {
+            match event {
+                Event::PieceFalling(piece, _) => {
+                    if !self.falling {
+                        self.falling = true;
+                        self.checker.piece_spawned(board, *piece);
+                    }
+                }
+                Event::PieceHeld(_) => {
+                    self.falling = false;
+                    self.checker.piece_held();
+                }
+                Event::PiecePlaced { piece, .. } => {
+                    self.falling = false;
+                    fault = self.checker.piece_placed(*piece);
+                }
+                _ => {}
+            }
+        }
+        fault
+    }
+}
@@ -14,8 +14,11 @@ pub struct Game {
     left_das: u32,
     right_das: u32,
     going_right: bool,
+    garbage: GarbageGenerator,
     pub garbage_queue: u32,
     pub attacking: u32,
+    pub statistics: Statistics,
+    ticks: u64,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
@@ -39,7 +42,8 @@ pub enum Event {
         hard_drop_distance: Option<i32>,
     },
     GarbageSent(u32),
-    GarbageAdded(Vec<usize>),
+    /// The holes of each row of garbage, in the order they were added.
+    GarbageAdded(Vec<Vec<usize>>),
     GameOver,
 }
 
@@ -62,7 +66,11 @@ struct FallingState {
 
 impl Game {
     pub fn new(config: GameConfig, piece_rng: &mut impl Rng) -> Self {
-        let mut board = Board::new();
+        let mut board = Board::with_size(config.width, config.height);
+        board.spin_rule = config.spin_rule;
+        board.attack = config.attack;
+        board.randomizer = config.randomizer;
+        board.top_out = config.top_out;
         for _ in 0..config.next_queue_size {
             board.add_next_piece(board.generate_next_piece(piece_rng));
         }
@@ -76,8 +84,11 @@ impl Game {
             right_das: config.delayed_auto_shift,
             going_right: false,
             state: GameState::SpawnDelay(config.spawn_delay),
+            garbage: GarbageGenerator::new(config.garbage, config.width),
             garbage_queue: 0,
             attacking: 0,
+            statistics: Statistics::default(),
+            ticks: 0,
         }
     }
 
@@ -87,6 +98,7 @@ impl Game {
         piece_rng: &mut impl Rng,
         garbage_rng: &mut impl Rng,
     ) -> Vec<Event> {
+        self.ticks += 1;
         update_input(&mut self.used.left, self.prev.left, current.left);
         update_input(&mut self.used.right, self.prev.right, current.right);
         update_input(
@@ -99,6 +111,11 @@ impl Game {
             self.prev.rotate_left,
             current.rotate_left,
         );
+        update_input(
+            &mut self.used.rotate_180,
+            self.prev.rotate_180,
+            current.rotate_180,
+        );
         update_input(
             &mut self.used.soft_drop,
             self.prev.soft_drop,
@@ -167,7 +184,9 @@ impl Game {
                 let new_piece = self.board.generate_next_piece(piece_rng);
                 self.board.add_next_piece(new_piece);
                 let next_piece = self.board.advance_queue().unwrap();
-                if let Some(spawned) = SpawnRule::Row19Or20.spawn(next_piece, &self.board) {
+                if let Some(spawned) =
+                    SpawnRule::Row19Or20.spawn(next_piece, &self.board, self.config.rotation_system)
+                {
                     self.state = GameState::Falling(FallingState {
                         piece: spawned,
                         lowest_y: spawned.cells().iter().map(|&(_, y)| y).min().unwrap(),
@@ -219,10 +238,15 @@ impl Game {
                 // Hold
                 if !self.did_hold && self.used.hold {
                     self.did_hold = true;
+                    self.statistics.hold_used();
                     events.push(Event::PieceHeld(falling.piece.kind.0));
                     if let Some(piece) = self.board.hold(falling.piece.kind.0) {
                         // Piece in hold; the piece spawns instantly
-                        if let Some(spawned) = SpawnRule::Row19Or20.spawn(piece, &self.board) {
+                        if let Some(spawned) = SpawnRule::Row19Or20.spawn(
+                            piece,
+                            &self.board,
+                            self.config.rotation_system,
+                        ) {
                             *falling = FallingState {
                                 piece: spawned,
                                 lowest_y: spawned.cells().iter().map(|&(_, y)| y).min().unwrap(),
@@ -248,7 +272,7 @@ impl Game {
 
                 // Rotate
                 if self.used.rotate_right {
-                    if falling.piece.cw(&self.board) {
+                    if falling.piece.cw(&self.board, self.config.rotation_system) {
                         self.used.rotate_right = false;
                         falling.rotation_move_count += 1;
                         falling.lock_delay = self.config.lock_delay;
@@ -260,7 +284,7 @@ impl Game {
                     }
                 }
                 if self.used.rotate_left {
-                    if falling.piece.ccw(&self.board) {
+                    if falling.piece.ccw(&self.board, self.config.rotation_system) {
                         self.used.rotate_left = false;
                         falling.rotation_move_count += 1;
                         falling.lock_delay = self.config.lock_delay;
@@ -272,6 +296,24 @@ impl Game {
                     }
                 }
 
+                if self.used.rotate_180 {
+                    if let Some(flip_kicks) = self.config.flip_kicks {
+                        if falling
+                            .piece
+                            .flip(&self.board, self.config.rotation_system, flip_kicks)
+                        {
+                            self.used.rotate_180 = false;
+                            falling.rotation_move_count += 1;
+                            falling.lock_delay = self.config.lock_delay;
+                            if falling.piece.tspin != TspinStatus::None {
+                                events.push(Event::PieceTSpined);
+                            } else {
+                                events.push(Event::PieceRotated);
+                            }
+                        }
+                    }
+                }
+
                 // Shift
                 while self.used.left && falling.piece.shift(&self.board, -1, 0) {
                     self.used.left = self.config.auto_repeat_rate == 0 && self.left_das == 0;
@@ -382,6 +424,7 @@ impl Game {
     ) {
         self.did_hold = false;
         let locked = self.board.lock_piece(falling.piece);
+        self.statistics.update_at(&locked, self.ticks);
 
         events.push(Event::PiecePlaced {
             piece: falling.piece,
@@ -410,18 +453,11 @@ impl Game {
             self.attacking = 0;
         }
         if self.garbage_queue > 0 {
-            let mut dead = false;
-            let mut col = rng.gen_range(0, 10);
-            let mut garbage_columns = vec![];
-            for _ in 0..self.garbage_queue.min(self.config.max_garbage_add) {
-                if rng.gen_bool(self.config.garbage_messiness.into_inner()) {
-                    col = rng.gen_range(0, 10);
-                }
-                garbage_columns.push(col);
-                dead |= self.board.add_garbage(col);
-            }
-            self.garbage_queue -= self.garbage_queue.min(self.config.max_garbage_add);
-            events.push(Event::GarbageAdded(garbage_columns));
+            let rows = self.garbage_queue.min(self.config.max_garbage_add);
+            let (garbage, dead) = self.garbage.add_to(&mut self.board, rows, rng);
+            self.garbage_queue -= rows;
+            self.statistics.garbage_added(rows as u64);
+            events.push(Event::GarbageAdded(garbage));
             if dead {
                 events.push(Event::GameOver);
                 self.state = GameState::GameOver;
@@ -1,10 +1,15 @@
-use ordered_float::NotNan;
-use serde::{Deserialize, Serialize};
+use libtetris::{
+    AttackTable, FlipKicks, GarbageRules, MovementCosts, PieceRandomizer, RotationSystem, SpinRule,
+    TopOutRules,
+};
+use serde::{Deserialize, Deserializer, Serialize};
 
 mod battle;
 pub use battle::{Battle, BattleUpdate, PlayerUpdate, Replay};
 mod controller;
 pub use controller::PieceMoveExecutor;
+mod finesse;
+pub use finesse::FinesseTracker;
 mod game;
 pub use game::{Event, Game};
 
@@ -12,6 +17,10 @@ pub use game::{Event, Game};
 #[derive(Copy, Clone, Debug, Hash, Eq, PartialEq, Serialize, Deserialize)]
 #[serde(default)]
 pub struct GameConfig {
+    /// Measured in cells. At most `libtetris::MAX_WIDTH`.
+    pub width: usize,
+    /// Measured in cells. At most `libtetris::MAX_HEIGHT`.
+    pub height: usize,
     pub spawn_delay: u32,
     pub line_clear_delay: u32,
     pub delayed_auto_shift: u32,
@@ -25,13 +34,24 @@ pub struct GameConfig {
     pub max_garbage_add: u32,
     pub move_lock_rule: u32,
     pub garbage_blocking: bool,
-    pub garbage_messiness: NotNan<f64>,
+    /// Configs from before garbage rules existed have a `garbage_messiness` number instead.
+    #[serde(alias = "garbage_messiness", deserialize_with = "deserialize_garbage")]
+    pub garbage: GarbageRules,
+    pub rotation_system: RotationSystem,
+    /// `None` disables the 180 button
+    pub flip_kicks: Option<FlipKicks>,
+    pub spin_rule: SpinRule,
+    pub attack: AttackTable,
+    pub randomizer: PieceRandomizer,
+    pub top_out: TopOutRules,
 }
 
 impl Default for GameConfig {
     fn default() -> Self {
         // Use something approximating Puyo Puyo Tetris
         GameConfig {
+            width: 10,
+            height: 40,
             spawn_delay: 7,
             line_clear_delay: 35,
             delayed_auto_shift: 9,
@@ -43,14 +63,53 @@ impl Default for GameConfig {
             max_garbage_add: 10,
             move_lock_rule: 15,
             garbage_blocking: false,
-            garbage_messiness: NotNan::new(0.3).unwrap(),
+            garbage: GarbageRules::messy(0.3),
+            rotation_system: RotationSystem::Srs,
+            flip_kicks: None,
+            spin_rule: SpinRule::TSpinsOnly,
+            attack: AttackTable::ppt(),
+            randomizer: PieceRandomizer::default(),
+            top_out: TopOutRules::default(),
         }
     }
 }
 
+/// Reads garbage rules, or the messiness number older configs have in their place. Only
+/// self-describing formats can hold either, so other formats always read garbage rules.
+fn deserialize_garbage<'de, D: Deserializer<'de>>(d: D) -> Result<GarbageRules, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Garbage {
+        Messiness(f64),
+        Rules(GarbageRules),
+    }
+
+    if !d.is_human_readable() {
+        return GarbageRules::deserialize(d);
+    }
+    match Garbage::deserialize(d)? {
+        Garbage::Messiness(m) if m.is_nan() => {
+            Err(serde::de::Error::custom("garbage messiness is NaN"))
+        }
+        Garbage::Messiness(m) => Ok(GarbageRules::messy(m)),
+        Garbage::Rules(rules) => Ok(rules),
+    }
+}
+
 impl GameConfig {
+    /// The movement costs a bot playing with this configuration should use.
+    pub fn movement_costs(&self) -> MovementCosts {
+        MovementCosts {
+            delayed_auto_shift: self.delayed_auto_shift,
+            auto_repeat_rate: self.auto_repeat_rate,
+            soft_drop_speed: self.soft_drop_speed,
+        }
+    }
+
     pub fn fast_config() -> Self {
         GameConfig {
+            width: 10,
+            height: 40,
             spawn_delay: 0,
             line_clear_delay: 0,
             delayed_auto_shift: 8,
@@ -62,7 +121,13 @@ impl GameConfig {
             max_garbage_add: 20,
             move_lock_rule: 15,
             garbage_blocking: true,
-            garbage_messiness: NotNan::new(0.0).unwrap(),
+            garbage: GarbageRules::clean(),
+            rotation_system: RotationSystem::Srs,
+            flip_kicks: None,
+            spin_rule: SpinRule::TSpinsOnly,
+            attack: AttackTable::ppt(),
+            randomizer: PieceRandomizer::default(),
+            top_out: TopOutRules::default(),
         }
     }
 }
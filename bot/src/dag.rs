@@ -78,18 +78,21 @@
 #![allow(dead_code)]
 
 use std::collections::{HashMap, VecDeque};
+use std::hash::{BuildHasherDefault, Hash, Hasher};
 use std::ops::ControlFlow;
+use std::sync::Arc;
 
 use arrayvec::ArrayVec;
 use bumpalo::collections::vec::Vec as BumpVec;
 use enum_map::EnumMap;
 use enumset::EnumSet;
-use libtetris::{Board, FallingPiece, LockResult, Piece};
+use libtetris::{Board, FallingPiece, LockResult, LockUndo, Piece};
 use ouroboros::self_referencing;
 use rand::prelude::*;
 use serde::{Deserialize, Serialize};
 
 use crate::evaluation::Evaluation;
+use crate::selection::{Candidate, SelectionPolicy};
 
 use self::ouroboros_impl_generation::BorrowedMutFields;
 pub struct DagState<E: 'static, R: 'static> {
@@ -98,7 +101,7 @@ pub struct DagState<E: 'static, R: 'static> {
     root: u32,
     gens_passed: u32,
     use_hold: bool,
-    is_bot_new:u32,
+    selection: Arc<dyn SelectionPolicy>,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -123,6 +126,9 @@ pub struct MoveCandidate<E> {
     pub evaluation: E,
     pub hold: bool,
     pub original_rank: u32,
+    /// The number of expansions of the move's node or its descendants.
+    pub visits: u32,
+    node: u32,
 }
 
 #[self_referencing]
@@ -136,14 +142,15 @@ struct Generation<E: 'static, R: 'static> {
 struct GenerationData<'c, E, R> {
     nodes: Vec<Node<'c, E>>,
     children: Children<'c, R>,
-    deduplicator: HashMap<SimplifiedBoard<'c>, u32>,
+    deduplicator: HashMap<SimplifiedBoard<'c>, u32, BuildHasherDefault<ZobristHasher>>,
 }
 
 enum Children<'c, R> {
     // we need to know the piece to resolve speculations computed before a new piece was added,
     // but given to us after a new piece was added.
     Known(Piece, Vec<Option<&'c mut [Child<R>]>>),
-    Speculated(Vec<Option<EnumMap<Piece, Option<&'c mut [Child<R>]>>>>),
+    // each possible piece is stored with its relative probability according to the randomizer.
+    Speculated(Vec<Option<EnumMap<Piece, Option<(u32, &'c mut [Child<R>])>>>>),
 }
 
 struct Node<'c, E> {
@@ -162,25 +169,54 @@ struct Child<R> {
     node: u32,
 }
 
-#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+/// Boards are hashed by their Zobrist hash alone. Boards with equal hashes are still compared
+/// field by field, so a collision can't merge two different boards.
+#[derive(Clone, Debug, Eq, PartialEq)]
 struct SimplifiedBoard<'c> {
+    hash: u64,
     grid: &'c [u16],
     combo: u32,
     bag: EnumSet<Piece>,
     reserve: Piece,
-    back_to_back: bool,
+    back_to_back: u32,
     reserve_is_hold: bool,
 }
 
+impl Hash for SimplifiedBoard<'_> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        state.write_u64(self.hash);
+    }
+}
+
+/// The Zobrist hash is already well mixed, so it's used as the hashmap hash as is.
+#[derive(Default)]
+struct ZobristHasher(u64);
+
+impl Hasher for ZobristHasher {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for &b in bytes {
+            self.0 = self.0.rotate_left(8) ^ b as u64;
+        }
+    }
+
+    fn write_u64(&mut self, hash: u64) {
+        self.0 = hash;
+    }
+}
+
 impl<E: Evaluation<R> + 'static, R: Clone + 'static> DagState<E, R> {//DagStateインスタンスを初期化
-    pub fn new(board: Board, use_hold: bool,is_bot_new:u32,) -> Self {
+    pub fn new(board: Board, use_hold: bool, selection: Arc<dyn SelectionPolicy>) -> Self {
         let mut this = DagState {
             board,
             generations: VecDeque::new(),
             root: 0,
             gens_passed: 0,
             use_hold,
-            is_bot_new,
+            selection,
         };
         this.init_generations();//初期化
         this
@@ -212,7 +248,7 @@ impl<E: Evaluation<R> + 'static, R: Clone + 'static> DagState<E, R> {//DagState
                     },
                     // nothing new will ever be put in the root generation, so we won't bother to
                     // put anything in the hashmap.
-                    deduplicator: HashMap::new(),
+                    deduplicator: HashMap::default(),
                 }
             }));
         // initialize the remaining known generations
@@ -255,20 +291,26 @@ impl<E: Evaluation<R> + 'static, R: Clone + 'static> DagState<E, R> {//DagState
                 return choice;//最適な手を探し終わったことを示す。
             }
         }
-        let isbotnew= self.is_bot_new;
+        let selection = self.selection.clone();
         self.find_and_mark_leaf_with_chooser(|next_gen_nodes, children| {
             // Since children is sorted best-to-worst, the minimum evaluation will be the last item
             // in the iterator. filter_map allows us to ignore death nodes.
             let evaluation = &child_eval_fn(next_gen_nodes);
             let min_eval = children.iter().rev().filter_map(evaluation).next()?;
-            //ここだ！！！！！！！！！！！！！！！！！！！！！！
-            let weights = children
+            let (children, candidates): (Vec<_>, Vec<_>) = children
                 .iter()
                 .enumerate()
-                .map(|(i, c)| evaluation(c).map_or(0, |e| e.weight(&min_eval, i,isbotnew,0)));
-            // Choose a node randomly (the Monte-Carlo part)
-            let sampler = rand::distributions::WeightedIndex::new(weights).ok()?;
-            Some(&children[thread_rng().sample(sampler)])
+                .filter_map(|(i, c)| {
+                    let candidate = Candidate {
+                        rank: i,
+                        advantage: evaluation(c)?.advantage(&min_eval),
+                        visits: next_gen_nodes[c.node as usize].visits,
+                        wins: next_gen_nodes[c.node as usize].wins,
+                    };
+                    Some((c, candidate))
+                })
+                .unzip();
+            Some(children[selection.choose(&candidates)?])
         })
     }
 
@@ -276,34 +318,39 @@ impl<E: Evaluation<R> + 'static, R: Clone + 'static> DagState<E, R> {//DagState
         &mut self,
         mut chooser: impl for<'a> FnMut(&[Node<E>], &'a [Child<R>]) -> Option<&'a Child<R>>,
     ) -> Option<(NodeId, Board)> {
-        let mut board = self.board.clone();// 現在のゲームボードのクローンを作成します。
+        // 現在のゲームボードをその場で進め、探索の終わりに元に戻します。
+        let board = &mut self.board;
+        let mut undos = vec![];
         let mut gen_index = 0;//現在の世代（ゲームツリー内の深さ）を示します。
         let mut node_key = self.root as usize;// 現在のノードのキーを示す変数を初期化します。
         let gens_passed = self.gens_passed;// 過去の世代の数を示す変数を取得します。
         loop {// 無限ループでゲームツリーを探索します。
-            let [current, next] = self.get_gen_and_next(gen_index);
+            let [current, next] = gen_and_next(&mut self.generations, gen_index);
             let result = current.with_data_mut(|gen| {
                 let children = match &gen.children {
-                    Children::Known(_, childrens) => childrens[node_key].as_deref(),
+                    Children::Known(_, childrens) => {
+                        childrens[node_key].as_deref().map(|c| (None, c))
+                    }
                     Children::Speculated(childrens) => {
                         childrens[node_key].as_ref().map(|children| {
                             let mut pick_from = ArrayVec::<[_; 7]>::new();
                             for (p, c) in children {
-                                if let Some(c) = c {
-                                    pick_from.push((p, &**c));
+                                if let Some((weight, c)) = c {
+                                    pick_from.push((p, *weight, &**c));
                                 }
                             }
-                            let (piece, children) = *pick_from.choose(&mut thread_rng()).unwrap();
-                            board.add_next_piece(piece);
-                            children
+                            let (piece, _, children) = *pick_from
+                                .choose_weighted(&mut thread_rng(), |&(_, weight, _)| weight)
+                                .unwrap();
+                            (Some(piece), children)
                         })
                     }
                 };
 
-                if let Some(children) = children {
+                if let Some((dealt, children)) = children {
                     match next.with_data(|gen| {
                         let child = chooser(&gen.nodes, children)?;
-                        advance(&mut board, child.placement);
+                        undos.push(advance_undoable(board, child.placement, dealt));
                         gen_index += 1;
                         node_key = child.node as usize;
                         Some(())
@@ -321,10 +368,12 @@ impl<E: Evaluation<R> + 'static, R: Clone + 'static> DagState<E, R> {//DagState
                     }))
                 }
             });
-            match result {
-                ControlFlow::Continue(()) => continue,
-                ControlFlow::Break(None) => return None,
-                ControlFlow::Break(Some(node)) => return Some((node, board)),
+            if let ControlFlow::Break(leaf) = result {
+                let leaf = leaf.map(|node| (node, board.clone()));
+                for undo in undos.into_iter().rev() {
+                    board.undo(undo);
+                }
+                return leaf;
             }
         }
     }
@@ -357,13 +406,14 @@ impl<E: Evaluation<R> + 'static, R: Clone + 'static> DagState<E, R> {//DagState
             })
         });
 
-        self.backpropogate(gen, vec![node.slab_key as usize]);
+        self.backpropogate(gen, vec![node.slab_key as usize], true);
     }
 
     pub fn update_speculated(
         &mut self,
         node: NodeId,
         mut children: EnumMap<Piece, Option<Vec<ChildData<E, R>>>>,
+        weights: EnumMap<Piece, u32>,
     ) {
         // make sure we weren't given a NodeId for an expired node. it could happen.
         if node.generation < self.gens_passed {
@@ -393,12 +443,15 @@ impl<E: Evaluation<R> + 'static, R: Clone + 'static> DagState<E, R> {//DagState
                         let mut childs = EnumMap::new();
                         for (p, data) in children {
                             if let Some(data) = data {
-                                childs[p] = Some(build_children(
-                                    current.arena,
-                                    &mut next,
-                                    data,
-                                    node.slab_key,
-                                    use_hold,
+                                childs[p] = Some((
+                                    weights[p],
+                                    build_children(
+                                        current.arena,
+                                        &mut next,
+                                        data,
+                                        node.slab_key,
+                                        use_hold,
+                                    ),
                                 ));
                             }
                         }
@@ -409,16 +462,32 @@ impl<E: Evaluation<R> + 'static, R: Clone + 'static> DagState<E, R> {//DagState
             })
         });
 
-        self.backpropogate(gen, vec![node.slab_key as usize]);
+        self.backpropogate(gen, vec![node.slab_key as usize], true);
     }
 
-    fn backpropogate(&mut self, mut gen: usize, mut to_update: Vec<usize>) {
+    /// Re-evaluates the nodes in `to_update` and their ancestors. With `visit`, the nodes were
+    /// just expanded, so the visit counts of them and all of their ancestors are incremented too.
+    fn backpropogate(&mut self, mut gen: usize, mut to_update: Vec<usize>, visit: bool) {
         // Use a queue to iterate in breadth-first order. This allows us to know that we shouldn't
         // add an element to the queue if it's already present; we know that all of its children
         // will have been processed first before we get to the parent node.
 
-        while !to_update.is_empty() {
+        // Ancestors whose evaluation doesn't change still need their visit counts incremented.
+        let mut to_visit: Vec<usize> = vec![];
+        while !to_update.is_empty() || !to_visit.is_empty() {
             let mut next_gen_to_update = vec![];
+            let mut next_gen_to_visit = vec![];
+            self.generations[gen].with_data_mut(|gen| {
+                for &node_id in &to_visit {
+                    let node = &mut gen.nodes[node_id];
+                    node.visits += 1;
+                    for &parent in &node.parents {
+                        if !next_gen_to_visit.contains(&(parent as usize)) {
+                            next_gen_to_visit.push(parent as usize);
+                        }
+                    }
+                }
+            });
             for node_id in to_update {
                 let [parent_gen, children_gen] = self.get_gen_and_next(gen);
                 parent_gen.with_data_mut(|parent_gen| {
@@ -454,31 +523,31 @@ impl<E: Evaluation<R> + 'static, R: Clone + 'static> DagState<E, R> {//DagState
                             new_eval
                         };
 
+                        // None if the node hasn't been expanded yet.
                         let new_eval = match &mut parent_gen.children {
                             Children::Known(_, children) => {
-                                if let Some(ref mut children) = children[node_id as usize].as_mut()
-                                {
-                                    process_children(children)
-                                } else {
-                                    // returns from closure and continues the loop
-                                    return;
-                                }
+                                children[node_id as usize].as_mut().map(process_children)
                             }
                             Children::Speculated(children) => {
-                                if let Some(children) = children[node_id as usize].as_mut() {
+                                children[node_id as usize].as_mut().map(|children| {
                                     // The eval of a speculated node should be the expected value,
-                                    // which is actually just the average since each piece in the bag
-                                    // has an equal probability of being chosen. So we'll track the
-                                    // number of pieces in the bag, the total score, etc. to calculate
-                                    // it later. We track the eval of the worst possibility to later
-                                    // use for death evaluations.
+                                    // so we weight each possibility by the probability of the
+                                    // randomizer dealing that piece. We track the eval of the worst
+                                    // possibility to later use for death evaluations.
+                                    let total_weight: u32 = children
+                                        .values()
+                                        .filter_map(Option::as_ref)
+                                        .map(|&(weight, _)| weight)
+                                        .sum();
                                     let mut possibilities = 0;
                                     let mut total = E::default();
                                     let mut deaths = 0;
                                     let mut worst = None;
-                                    for children in children.values_mut().filter_map(Option::as_mut)
+                                    for (weight, children) in
+                                        children.values_mut().filter_map(Option::as_mut)
                                     {
-                                        possibilities += 1;
+                                        let weight = speculation_weight(*weight, total_weight);
+                                        possibilities += weight;
                                         match process_children(children) {
                                             Some(eval) => {
                                                 match worst {
@@ -488,35 +557,47 @@ impl<E: Evaluation<R> + 'static, R: Clone + 'static> DagState<E, R> {//DagState
                                                     }
                                                     _ => {}
                                                 }
-                                                total = total + eval;
+                                                total = total + eval * weight;
                                             }
-                                            None => deaths += 1,
+                                            None => deaths += weight,
                                         }
                                     }
                                     worst.map(|worst| {
                                         (total + worst.modify_death() * deaths) / possibilities
                                     })
-                                } else {
-                                    // returns from closure and continues the loop
-                                    return;
-                                }
+                                })
                             }
                         };
 
-                        let continue_propogation = match &new_eval {
-                            Some(eval) => *eval != node.evaluation,
-                            None => !node.death,
+                        let (continue_propogation, improved) = match &new_eval {
+                            Some(Some(eval)) => (
+                                *eval != node.evaluation,
+                                !node.death && *eval > node.evaluation,
+                            ),
+                            Some(None) => (!node.death, false),
+                            None => (false, false),
                         };
-                        if continue_propogation {
-                            for &parent in &node.parents {
-                                if !next_gen_to_update.contains(&(parent as usize)) {
-                                    next_gen_to_update.push(parent as usize);
+                        if visit {
+                            node.visits += 1;
+                            // An expansion wins for the nodes whose evaluation it improved.
+                            if improved {
+                                node.wins += 1;
+                            }
+                        }
+                        for &parent in &node.parents {
+                            let parent = parent as usize;
+                            if continue_propogation {
+                                if !next_gen_to_update.contains(&parent) {
+                                    next_gen_to_update.push(parent);
                                 }
+                            } else if visit && !next_gen_to_visit.contains(&parent) {
+                                next_gen_to_visit.push(parent);
                             }
                         }
                         match new_eval {
-                            Some(eval) => node.evaluation = eval,
-                            None => node.death = true,
+                            Some(Some(eval)) => node.evaluation = eval,
+                            Some(None) => node.death = true,
+                            None => {}
                         }
                     })
                 });
@@ -524,33 +605,15 @@ impl<E: Evaluation<R> + 'static, R: Clone + 'static> DagState<E, R> {//DagState
             if gen == 0 {
                 break;
             }
+            next_gen_to_visit.retain(|node| !next_gen_to_update.contains(node));
             to_update = next_gen_to_update;
+            to_visit = next_gen_to_visit;
             gen -= 1;
         }
     }
 
     fn get_gen_and_next(&mut self, gen: usize) -> [&mut Generation<E, R>; 2] {
-        if gen == self.generations.len() - 1 {
-            // we're expanding into boards that belong in a generation that doesn't exist yet.
-            // since it doesn't exist, we're missing some next queue information, so it's a
-            // speculated generation.
-            self.generations.push_back(Generation::speculated());
-        }
-
-        fn get2_mut<T>(s: &mut [T], i: usize) -> [&mut T; 2] {
-            let (a, b) = s.split_at_mut(i + 1);
-            [&mut a[a.len() - 1], &mut b[0]]
-        }
-
-        // need to do something a little weird to get mutable references to both generations
-        let (a, b) = self.generations.as_mut_slices();
-        if a.len() - 1 == gen {
-            [&mut a[gen], &mut b[0]]
-        } else if a.len() > gen {
-            get2_mut(a, gen)
-        } else {
-            get2_mut(b, gen - a.len())
-        }
+        gen_and_next(&mut self.generations, gen)
     }
 
     pub fn unmark(&mut self, node: NodeId) {
@@ -570,8 +633,9 @@ impl<E: Evaluation<R> + 'static, R: Clone + 'static> DagState<E, R> {//DagState
                 Children::Speculated(childs) => {
                     let mut newchildren = Vec::with_capacity(childs.len());
                     for (j, child) in std::mem::take(childs).into_iter().enumerate() {
-                        newchildren
-                            .push(child.and_then(|mut cases| std::mem::take(&mut cases[piece])));
+                        newchildren.push(child.and_then(|mut cases| {
+                            std::mem::take(&mut cases[piece]).map(|(_, children)| children)
+                        }));
                         to_update.push(j);
                     }
                     gen.children = Children::Known(piece, newchildren);
@@ -580,7 +644,7 @@ impl<E: Evaluation<R> + 'static, R: Clone + 'static> DagState<E, R> {//DagState
                 _ => false,
             });
             if done {
-                self.backpropogate(i, to_update);
+                self.backpropogate(i, to_update, false);
                 return;
             }
         }
@@ -588,11 +652,49 @@ impl<E: Evaluation<R> + 'static, R: Clone + 'static> DagState<E, R> {//DagState
         self.generations.push_back(Generation::known(piece));
     }
 
+    /// The placement of the best move from the current position, if the root has been expanded.
+    pub fn best_move(&self) -> Option<FallingPiece> {
+        self.generations.front()?.with_data(|gen| match &gen.children {
+            Children::Known(_, c) => c[self.root as usize].as_ref()?.first().map(|c| c.placement),
+            _ => None,
+        })
+    }
+
+    /// The evaluation of `best_move`, including its reward.
+    pub fn best_evaluation(&self) -> Option<E> {
+        let child_gen = self.generations.get(1)?;
+        self.generations[0].with_data(|gen| match &gen.children {
+            Children::Known(_, c) => {
+                let child = c[self.root as usize].as_ref()?.first()?;
+                let eval = child_gen.with_data(|g| g.nodes[child.node as usize].evaluation.clone());
+                Some(eval + child.reward.clone())
+            }
+            _ => None,
+        })
+    }
+
     pub fn get_plan(&self) -> Vec<(FallingPiece, LockResult)> {
-        let mut node = self.root;
         let mut plan = vec![];
-        let mut board = self.board.clone();
-        for gen in &self.generations {
+        self.extend_plan(&mut plan, 0, self.root, self.board.clone());
+        plan
+    }
+
+    /// The plan starting with the given candidate move.
+    pub fn get_plan_after(&self, candidate: &MoveCandidate<E>) -> Vec<(FallingPiece, LockResult)> {
+        let mut plan = vec![(candidate.mv, candidate.lock.clone())];
+        self.extend_plan(&mut plan, 1, candidate.node, candidate.board.clone());
+        plan
+    }
+
+    /// Follows the best moves from the node in the given generation.
+    fn extend_plan(
+        &self,
+        plan: &mut Vec<(FallingPiece, LockResult)>,
+        gen: usize,
+        mut node: u32,
+        mut board: Board,
+    ) {
+        for gen in self.generations.iter().skip(gen) {
             let done = gen.with_data(|gen| match &gen.children {
                 Children::Known(_, c) => match c[node as usize].as_ref().and_then(|c| c.first()) {
                     Some(child) => {
@@ -608,12 +710,11 @@ impl<E: Evaluation<R> + 'static, R: Clone + 'static> DagState<E, R> {//DagState
                 break;
             }
         }
-        plan
     }
 
     pub fn reset(&mut self, field: [[bool; 10]; 40], b2b: bool, combo: u32) -> Option<i32> {
         let garbage_lines;
-        if b2b == self.board.b2b_bonus && combo == self.board.combo {
+        if b2b == self.board.b2b_bonus() && combo == self.board.combo {
             let mut b = Board::<u16>::new();
             b.set_field(field);
             let dif = self
@@ -625,7 +726,7 @@ impl<E: Evaluation<R> + 'static, R: Clone + 'static> DagState<E, R> {//DagState
                 .min()
                 .unwrap();
             let mut is_garbage_receive = true;
-            for y in 0..(40 - dif) {
+            for y in 0..(self.board.height() - dif) {
                 if b.get_row(y + dif) != self.board.get_row(y) {
                     is_garbage_receive = false;
                     break;
@@ -642,7 +743,7 @@ impl<E: Evaluation<R> + 'static, R: Clone + 'static> DagState<E, R> {//DagState
 
         self.board.set_field(field);
         self.board.combo = combo;
-        self.board.b2b_bonus = b2b;
+        self.board.set_b2b_bonus(b2b);
 
         self.gens_passed += self.generations.len() as u32 + 1;
         self.root = 0;
@@ -673,6 +774,8 @@ impl<E: Evaluation<R> + 'static, R: Clone + 'static> DagState<E, R> {//DagState
                                 hold: self.board.hold_piece != board.hold_piece,
                                 evaluation: eval + child.reward.clone(),
                                 original_rank: i as u32,
+                                visits: child_gen.nodes[child.node as usize].visits,
+                                node: child.node,
                                 lock,
                                 board,
                             });
@@ -722,6 +825,14 @@ impl<E: Evaluation<R> + 'static, R: Clone + 'static> DagState<E, R> {//DagState
             .sum()
     }
 
+    /// The memory allocated by the generations' arenas, in bytes.
+    pub fn arena_bytes(&self) -> usize {
+        self.generations
+            .iter()
+            .map(|gen| gen.with_arena(|arena| arena.allocated_bytes()))
+            .sum()
+    }
+
     pub fn depth(&self) -> u32 {
         let mut depth = self.generations.len() as u32 - 1;
         for gen in self.generations.iter().rev() {
@@ -753,6 +864,14 @@ impl<E: Evaluation<R> + 'static, R: Clone + 'static> DagState<E, R> {//DagState
     }
 }
 
+/// Scales the relative probability of a speculated piece to a small integer so that weighting
+/// evaluations by it can't overflow.
+fn speculation_weight(weight: u32, total_weight: u32) -> usize {
+    const PRECISION: u64 = 64;
+    let total_weight = total_weight as u64;
+    ((weight as u64 * PRECISION + total_weight / 2) / total_weight).max(1) as usize
+}
+
 fn child_eval_fn<'a, E, R>(child_gen_nodes: &'a [Node<E>]) -> impl Fn(&Child<R>) -> Option<E> + 'a
 where
     E: Evaluation<R>,
@@ -768,16 +887,60 @@ where
     }
 }
 
+/// Gets a generation and the one after it, borrowing only the generations so the board can be
+/// used at the same time.
+fn gen_and_next<E: 'static, R: 'static>(
+    generations: &mut VecDeque<Generation<E, R>>,
+    gen: usize,
+) -> [&mut Generation<E, R>; 2] {
+    if gen == generations.len() - 1 {
+        // we're expanding into boards that belong in a generation that doesn't exist yet.
+        // since it doesn't exist, we're missing some next queue information, so it's a
+        // speculated generation.
+        generations.push_back(Generation::speculated());
+    }
+
+    fn get2_mut<T>(s: &mut [T], i: usize) -> [&mut T; 2] {
+        let (a, b) = s.split_at_mut(i + 1);
+        [&mut a[a.len() - 1], &mut b[0]]
+    }
+
+    // need to do something a little weird to get mutable references to both generations
+    let (a, b) = generations.as_mut_slices();
+    if a.len() - 1 == gen {
+        [&mut a[gen], &mut b[0]]
+    } else if a.len() > gen {
+        get2_mut(a, gen)
+    } else {
+        get2_mut(b, gen - a.len())
+    }
+}
+
+/// Like `advance`, but returns what is needed to undo the placement. `dealt` is a speculated piece
+/// to add to the queue; it is added after the lock so that undoing takes it back too.
+fn advance_undoable(board: &mut Board, placement: FallingPiece, dealt: Option<Piece>) -> LockUndo {
+    let (_, undo) = board.lock_piece_undoable(placement);
+    if let Some(piece) = dealt {
+        board.add_next_piece(piece);
+    }
+    advance_queue(board, placement);
+    undo
+}
+
 /// keeps queue state consistent while arbitrarily placing pieces
 fn advance(board: &mut Board, placement: FallingPiece) -> LockResult {
     let result = board.lock_piece(placement);
+    advance_queue(board, placement);
+    result
+}
+
+fn advance_queue(board: &mut Board, placement: FallingPiece) {
     let next = board.advance_queue().unwrap();
     if next != placement.kind.0 {
         let unheld = board.hold(next);
         let p = unheld.unwrap_or_else(|| board.advance_queue().unwrap());
         assert_eq!(p, placement.kind.0);
     }
-    result
 }
 
 fn build_children<'arena, E: Evaluation<R> + 'static, R: Clone + 'static>(
@@ -799,8 +962,9 @@ fn build_children<'arena, E: Evaluation<R> + 'static, R: Clone + 'static>(
         }
 
         let simple_board = SimplifiedBoard {
+            hash: data.board.zobrist_hash(),
             grid: &simple_grid,
-            back_to_back: data.board.b2b_bonus,
+            back_to_back: data.board.b2b_chain,
             combo: data.board.combo,
             bag: data.board.next_bag(),
             reserve: if hold_allowed {
@@ -857,7 +1021,7 @@ impl<E: 'static, R: 'static> Generation<E, R> {
         Generation::new(Box::new(bumpalo::Bump::with_capacity(1 << 20)), |_| {
             GenerationData {
                 nodes: Vec::with_capacity(1 << 17),
-                deduplicator: HashMap::with_capacity(1 << 17),
+                deduplicator: HashMap::with_capacity_and_hasher(1 << 17, Default::default()),
                 children: Children::Known(piece, Vec::with_capacity(1 << 17)),
             }
         })
@@ -867,7 +1031,7 @@ impl<E: 'static, R: 'static> Generation<E, R> {
         Generation::new(Box::new(bumpalo::Bump::with_capacity(1 << 20)), |_| {
             GenerationData {
                 nodes: Vec::with_capacity(1 << 17),
-                deduplicator: HashMap::with_capacity(1 << 17),
+                deduplicator: HashMap::with_capacity_and_hasher(1 << 17, Default::default()),
                 children: Children::Speculated(Vec::with_capacity(1 << 17)),
             }
         })
@@ -1,16 +1,19 @@
 use std::sync::Arc;
+use std::time::Duration;
 
-use crossbeam_channel::{select, unbounded, Receiver, Sender, TryRecvError};
+use crossbeam_channel::{after, never, select, tick, unbounded, Receiver, Sender, TryRecvError};
 use libtetris::*;
 use opening_book::Book;
 
 use crate::evaluation::Evaluator;
 use crate::modes::ModeSwitchedBot;
-use crate::{BotMsg, BotPollState, Info, Options};
+use crate::{AnalysisLine, BotMsg, BotPollState, Info, Options, SearchStats};
 
 pub struct Interface {
     send: Sender<BotMsg>,
     recv: Receiver<(Move, Info)>,
+    analysis: Receiver<Vec<AnalysisLine>>,
+    stats: Receiver<SearchStats>,
 }
 
 impl Interface {
@@ -23,9 +26,27 @@ impl Interface {
     ) -> Self {
         let (bot_send, recv) = unbounded();
         let (send, bot_recv) = unbounded();
-        std::thread::spawn(move || run(bot_recv, bot_send, board, evaluator, options, book));
+        let (analysis_send, analysis) = unbounded();
+        let (stats_send, stats) = unbounded();
+        std::thread::spawn(move || {
+            run(
+                bot_recv,
+                bot_send,
+                analysis_send,
+                stats_send,
+                board,
+                evaluator,
+                options,
+                book,
+            )
+        });
 
-        Interface { send, recv }
+        Interface {
+            send,
+            recv,
+            analysis,
+            stats,
+        }
     }
 
     /// Request the bot to provide a move as soon as possible.
@@ -34,6 +55,10 @@ impl Interface {
     /// the provided lower limit on thinking has not been reached yet or if the bot cannot provide
     /// a move yet, usually because it lacks information on the next pieces.
     ///
+    /// If `think_time` is given, the bot instead keeps thinking for up to that long before
+    /// answering, as with `Options::max_think_time`. It answers early if its best move stops
+    /// changing.
+    ///
     /// For example, in a game with zero piece previews and hold enabled, the bot will never be able
     /// to provide the first move because it cannot know what piece it will be placing if it chooses
     /// to hold. Another example: in a game with zero piece previews and hold disabled, the bot
@@ -45,8 +70,10 @@ impl Interface {
     ///
     /// Once a move is chosen, the move will become available by calling `poll_next_move` or
     /// `block_next_move`. To update the bot state according to this move, call `play_next_move`.
-    pub fn suggest_next_move(&self, incoming: u32) {
-        self.send.send(BotMsg::SuggestMove(incoming)).ok();
+    pub fn suggest_next_move(&self, incoming: u32, think_time: Option<Duration>) {
+        self.send
+            .send(BotMsg::SuggestMove(incoming, think_time))
+            .ok();
     }
 
     /// Checks to see if the bot has provided the previously requested move yet.
@@ -104,6 +131,29 @@ impl Interface {
             .ok();
     }
 
+    /// Waits for the bot's current top `count` candidate moves, best first, without choosing or
+    /// playing one. The bot keeps thinking afterwards.
+    ///
+    /// The list is empty if the bot hasn't found any moves yet. `None` is returned if the bot is
+    /// dead.
+    pub fn request_analysis(&self, count: usize) -> Option<Vec<AnalysisLine>> {
+        self.send.send(BotMsg::RequestAnalysis(count)).ok()?;
+        self.analysis.recv().ok()
+    }
+
+    /// Starts or stops sending a snapshot of the search every `interval`. Snapshots become
+    /// available by calling `poll_stats`.
+    pub fn enable_stats(&self, interval: Option<Duration>) {
+        self.send.send(BotMsg::SetStatsInterval(interval)).ok();
+    }
+
+    /// Returns the most recent search snapshot sent since the last call, if any.
+    ///
+    /// No snapshots are sent while the bot is in PC loop mode.
+    pub fn poll_stats(&self) -> Option<SearchStats> {
+        self.stats.try_iter().last()
+    }
+
     /// Specifies a line that Cold Clear should analyze before making any moves.
     pub fn force_analysis_line(&self, path: Vec<FallingPiece>) {
         self.send.send(BotMsg::ForceAnalysisLine(path)).ok();
@@ -113,6 +163,8 @@ impl Interface {
 fn run(
     recv: Receiver<BotMsg>,
     send: Sender<(Move, Info)>,
+    analysis_send: Sender<Vec<AnalysisLine>>,
+    stats_send: Sender<SearchStats>,
     mut board: Board,
     eval: impl Evaluator + 'static,
     options: Options,
@@ -122,6 +174,7 @@ fn run(
         panic!("Invalid number of threads: 0");
     }
 
+    let mut stats_interval = None;
     while board.next_queue().next().is_none() {
         match recv.recv() {
             Err(_) => return,
@@ -129,11 +182,15 @@ fn run(
             Ok(BotMsg::Reset { field, b2b, combo }) => {
                 board.set_field(field);
                 board.combo = combo;
-                board.b2b_bonus = b2b;
+                board.set_b2b_bonus(b2b);
             }
-            Ok(BotMsg::SuggestMove(_)) => {}
+            Ok(BotMsg::SuggestMove(..)) => {}
             Ok(BotMsg::ForceAnalysisLine(_)) => {}
             Ok(BotMsg::PlayMove(_)) => {}
+            Ok(BotMsg::RequestAnalysis(_)) => {
+                analysis_send.send(vec![]).ok();
+            }
+            Ok(BotMsg::SetStatsInterval(interval)) => stats_interval = interval,
         }
     }
 
@@ -146,11 +203,20 @@ fn run(
 
     let (result_send, result_recv) = unbounded();
 
+    let ticker = |interval: Option<Duration>| interval.map_or_else(never, tick);
+    let mut stats_ticker = ticker(stats_interval);
+
     let eval = Arc::new(eval);
     loop {
-        let new_tasks = bot.think(&eval, |result| {
-            send.send(result).ok();
-        });
+        let new_tasks = bot.think(
+            &eval,
+            |result| {
+                send.send(result).ok();
+            },
+            |analysis| {
+                analysis_send.send(analysis).ok();
+            },
+        );
         for task in new_tasks {
             let result_send = result_send.clone();
             let eval = eval.clone();
@@ -159,9 +225,17 @@ fn run(
             });
         }
 
+        // Otherwise a move request only times out when a task completes or a message arrives,
+        // which can be long after its deadline when no thinks are running.
+        let deadline = bot.move_deadline().map_or_else(never, after);
         select! {
             recv(result_recv) -> result => bot.task_complete(result.unwrap()),
+            recv(deadline) -> _ => {},
+            recv(stats_ticker) -> _ => if let Some(stats) = bot.stats() {
+                stats_send.send(stats).ok();
+            },
             recv(recv) -> msg => match msg {
+                Ok(BotMsg::SetStatsInterval(interval)) => stats_ticker = ticker(interval),
                 Ok(msg) => bot.message(msg),
                 Err(_) => break
             }
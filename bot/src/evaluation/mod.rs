@@ -12,10 +12,12 @@ pub trait Evaluator: Send + Sync {//Evaluatorのトレイト　マルチスレ
 
     fn name(&self) -> String;
 
+    /// Evaluates `board`, the board after a placement. The board may be changed while evaluating
+    /// it, but must be left as it was given.
     fn evaluate(
         &self,
         lock: &LockResult,
-        board: &Board,
+        board: &mut Board,
         move_time: u32,
         placed: Piece,
     ) -> (Self::Value, Self::Reward);
@@ -40,7 +42,10 @@ pub trait Evaluation<R>:
     + std::ops::Add<Output = Self>
 {
     fn modify_death(self) -> Self;
-    fn weight(self, min: &Self, rank: usize,is_bot_new:u32,visits:u32) -> i64;
+    /// How much better this evaluation is than `worst`, used to choose which children to search.
+    fn advantage(&self, worst: &Self) -> i64;
+    /// The evaluation as a single number, for reporting analysis.
+    fn score(&self) -> i64;
 
     fn improve(&mut self, other: Self);
 }
@@ -56,7 +61,7 @@ impl<T: Evaluator> Evaluator for std::sync::Arc<T> {
     fn evaluate(
         &self,
         lock: &LockResult,
-        board: &Board,
+        board: &mut Board,
         move_time: u32,
         placed: Piece,
     ) -> (T::Value, T::Reward) {
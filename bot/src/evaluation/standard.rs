@@ -1,3 +1,4 @@
+use arrayvec::ArrayVec;
 use libtetris::*;
 use serde::{Deserialize, Serialize};
 
@@ -7,6 +8,7 @@ use super::*;
 #[serde(default)]
 pub struct Standard {
     pub back_to_back: i32,
+    pub b2b_chain: i32,
     pub bumpiness: i32,
     pub bumpiness_sq: i32,
     pub row_transitions: i32,
@@ -26,6 +28,7 @@ pub struct Standard {
     pub well_column: [i32; 10],
 
     pub b2b_clear: i32,
+    pub b2b_surge: i32,
     pub clear1: i32,
     pub clear2: i32,
     pub clear3: i32,
@@ -35,6 +38,13 @@ pub struct Standard {
     pub tspin3: i32,
     pub mini_tspin1: i32,
     pub mini_tspin2: i32,
+    pub mini_tspin3: i32,
+    pub mini_spin1: i32,
+    pub mini_spin2: i32,
+    pub mini_spin3: i32,
+    pub spin1: i32,
+    pub spin2: i32,
+    pub spin3: i32,
     pub perfect_clear: i32,
     pub combo_garbage: i32,
     pub move_time: i32,
@@ -50,6 +60,7 @@ impl Default for Standard {
     fn default() -> Self {
         Standard {
             back_to_back: 52,
+            b2b_chain: 0,
             bumpiness: -24,
             bumpiness_sq: -7,
             row_transitions: -5,
@@ -71,6 +82,7 @@ impl Default for Standard {
             move_time: -3,
             wasted_t: -152,
             b2b_clear: 104,
+            b2b_surge: 0,
             clear1: -143,
             clear2: -100,
             clear3: -58,
@@ -80,6 +92,13 @@ impl Default for Standard {
             tspin3: 602,
             mini_tspin1: -158,
             mini_tspin2: -93,
+            mini_tspin3: -58,
+            mini_spin1: -158,
+            mini_spin2: -93,
+            mini_spin3: -58,
+            spin1: 121,
+            spin2: 410,
+            spin3: 602,
             perfect_clear: 999,
             combo_garbage: 150,
 
@@ -95,6 +114,7 @@ impl Standard {
     pub fn fast_config() -> Self {
         Standard {
             back_to_back: 10,
+            b2b_chain: 0,
             bumpiness: -7,
             bumpiness_sq: -28,
             row_transitions: -5,
@@ -113,6 +133,7 @@ impl Standard {
             max_well_depth: -2,
             well_column: [31, 16, -41, 37, 49, 30, 56, 48, -27, 22],
             b2b_clear: 74,
+            b2b_surge: 0,
             clear1: -122,
             clear2: -174,
             clear3: 11,
@@ -122,6 +143,13 @@ impl Standard {
             tspin3: 628,
             mini_tspin1: -188,
             mini_tspin2: -682,
+            mini_tspin3: 11,
+            mini_spin1: -188,
+            mini_spin2: -682,
+            mini_spin3: 11,
+            spin1: 131,
+            spin2: 392,
+            spin3: 628,
             perfect_clear: 991,
             combo_garbage: 272,
             move_time: -1,
@@ -158,6 +186,11 @@ impl Evaluator for Standard {
                 || mv.board.column_heights()[3..6]
                     .iter()
                     .all(|h| incoming as i32 - mv.lock.garbage_sent as i32 + h <= 20)
+                    && (!mv.board.top_out.garbage_top_out
+                        || mv.board.column_heights().iter().all(|h| {
+                            incoming as i32 - mv.lock.garbage_sent as i32 + h
+                                <= mv.board.top_out.visible_rows
+                        }))
             {
                 return mv;
             }
@@ -175,7 +208,7 @@ impl Evaluator for Standard {
     fn evaluate(
         &self,
         lock: &LockResult,
-        board: &Board,
+        board: &mut Board,
         move_time: u32,
         placed: Piece,
     ) -> (Value, Reward) {
@@ -189,9 +222,13 @@ impl Evaluator for Standard {
             if lock.b2b {
                 acc_eval += self.b2b_clear;
             }
+            acc_eval += self.b2b_surge * lock.surge as i32;
             if let Some(combo) = lock.combo {
-                let combo = combo.min(11) as usize;
-                acc_eval += self.combo_garbage * libtetris::COMBO_GARBAGE[combo] as i32;
+                let mut base = board.attack.base_attack(lock.placement_kind);
+                if lock.b2b {
+                    base += board.attack.back_to_back_attack(lock.b2b_chain - 1);
+                }
+                acc_eval += self.combo_garbage * board.attack.combo_attack(base, combo) as i32;
             }
             match lock.placement_kind {
                 PlacementKind::Clear1 => {
@@ -221,6 +258,27 @@ impl Evaluator for Standard {
                 PlacementKind::MiniTspin2 => {
                     acc_eval += self.mini_tspin2;
                 }
+                PlacementKind::MiniTspin3 => {
+                    acc_eval += self.mini_tspin3;
+                }
+                PlacementKind::MiniSpin1 => {
+                    acc_eval += self.mini_spin1;
+                }
+                PlacementKind::MiniSpin2 => {
+                    acc_eval += self.mini_spin2;
+                }
+                PlacementKind::MiniSpin3 => {
+                    acc_eval += self.mini_spin3;
+                }
+                PlacementKind::Spin1 => {
+                    acc_eval += self.spin1;
+                }
+                PlacementKind::Spin2 => {
+                    acc_eval += self.spin2;
+                }
+                PlacementKind::Spin3 => {
+                    acc_eval += self.spin3;
+                }
                 _ => {}
             }
         }
@@ -240,13 +298,19 @@ impl Evaluator for Standard {
         };
         acc_eval += self.move_time * move_time;
 
-        if board.b2b_bonus {
+        if board.b2b_bonus() {
             transient_eval += self.back_to_back;
+            transient_eval += self.b2b_chain * (board.b2b_chain as i32 - 1);
         }
 
         let highest_point = *board.column_heights().iter().max().unwrap() as i32;
         transient_eval += self.top_quarter * (highest_point - 15).max(0);
         transient_eval += self.top_half * (highest_point - 10).max(0);
+        if board.top_out.garbage_top_out {
+            // Any garbage tops out a stack above the visible rows.
+            transient_eval +=
+                self.top_quarter * (highest_point - board.top_out.visible_rows).max(0);
+        }
 
         acc_eval += self.jeopardy
             * (highest_point - 10).max(0)
@@ -261,13 +325,14 @@ impl Evaluator for Standard {
             1 + (board.hold_piece == Some(Piece::T)) as usize
         };
 
-        let mut board = board.clone();
+        // T slots that clear at least two lines are cut out for the rest of the evaluation
+        let mut cutouts = ArrayVec::<[_; 3]>::new();
         for _ in 0..ts {
-            let cutout_location = sky_tslot_left(&board)
-                .or_else(|| sky_tslot_right(&board))
+            let cutout_location = sky_tslot_left(board)
+                .or_else(|| sky_tslot_right(board))
                 .or_else(|| {
-                    let tst = tst_twist_left(&board).or_else(|| tst_twist_right(&board))?;
-                    cave_tslot(&board, tst).or_else(|| {
+                    let tst = tst_twist_left(board).or_else(|| tst_twist_right(board))?;
+                    cave_tslot(board, tst).or_else(|| {
                         let corners = board.occupied(tst.x - 1, tst.y - 1) as usize
                             + board.occupied(tst.x + 1, tst.y - 1) as usize
                             + board.occupied(tst.x - 1, tst.y + 1) as usize
@@ -279,18 +344,18 @@ impl Evaluator for Standard {
                         }
                     })
                 })
-                .or_else(|| fin_left(&board))
-                .or_else(|| fin_right(&board));
-            let result = match cutout_location {
-                Some(location) => cutout_tslot(board.clone(), location),
+                .or_else(|| fin_left(board))
+                .or_else(|| fin_right(board));
+            let (lines, undo) = match cutout_location {
+                Some(location) => cutout_tslot(board, location),
                 None => break,
             };
-            transient_eval += self.tslot[result.lines];
-            if let Some(b) = result.result {
-                board = b;
-            } else {
+            transient_eval += self.tslot[lines];
+            if lines < 2 {
+                board.undo(undo);
                 break;
             }
+            cutouts.push(undo);
         }
 
         let highest_point = *board.column_heights().iter().max().unwrap() as i32;
@@ -328,7 +393,7 @@ impl Evaluator for Standard {
         }
 
         if self.bumpiness | self.bumpiness_sq != 0 {
-            let (bump, bump_sq) = bumpiness(&board, well);
+            let (bump, bump_sq) = bumpiness(board, well);
             transient_eval += bump * self.bumpiness;
             transient_eval += bump_sq * self.bumpiness_sq;
         }
@@ -336,7 +401,7 @@ impl Evaluator for Standard {
         if self.cavity_cells | self.cavity_cells_sq | self.overhang_cells | self.overhang_cells_sq
             != 0
         {
-            let (cavity_cells, overhang_cells) = cavities_and_overhangs(&board);
+            let (cavity_cells, overhang_cells) = cavities_and_overhangs(board);
             transient_eval += self.cavity_cells * cavity_cells;
             transient_eval += self.cavity_cells_sq * cavity_cells * cavity_cells;
             transient_eval += self.overhang_cells * overhang_cells;
@@ -344,11 +409,15 @@ impl Evaluator for Standard {
         }
 
         if self.covered_cells | self.covered_cells_sq != 0 {
-            let (covered_cells, covered_cells_sq) = covered_cells(&board);
+            let (covered_cells, covered_cells_sq) = covered_cells(board);
             transient_eval += self.covered_cells * covered_cells;
             transient_eval += self.covered_cells_sq * covered_cells_sq;
         }
 
+        for undo in cutouts.into_iter().rev() {
+            board.undo(undo);
+        }
+
         (
             Value {
                 value: transient_eval,
@@ -646,34 +715,19 @@ fn cave_tslot(board: &Board, mut starting_point: FallingPiece) -> Option<Falling
     }
 }
 
-struct Cutout {
-    lines: usize,
-    result: Option<Board>,
-}
-
-fn cutout_tslot(mut board: Board, mut piece: FallingPiece) -> Cutout {
+/// Places a T in the slot, returning the lines it clears and how to undo it.
+fn cutout_tslot(board: &mut Board, mut piece: FallingPiece) -> (usize, LockUndo) {
     piece.tspin = TspinStatus::Full;
-    let result = board.lock_piece(piece);
-
-    match result.placement_kind {
-        PlacementKind::Tspin => Cutout {
-            lines: 0,
-            result: None,
-        },
-        PlacementKind::Tspin1 => Cutout {
-            lines: 1,
-            result: None,
-        },
-        PlacementKind::Tspin2 => Cutout {
-            lines: 2,
-            result: Some(board),
-        },
-        PlacementKind::Tspin3 => Cutout {
-            lines: 3,
-            result: Some(board),
-        },
+    let (result, undo) = board.lock_piece_undoable(piece);
+
+    let lines = match result.placement_kind {
+        PlacementKind::Tspin => 0,
+        PlacementKind::Tspin1 => 1,
+        PlacementKind::Tspin2 => 2,
+        PlacementKind::Tspin3 => 3,
         _ => unreachable!(),
-    }
+    };
+    (lines, undo)
 }
 
 #[derive(Copy, Clone, Debug, Eq, PartialEq, Default, Serialize, Deserialize)]
@@ -740,17 +794,12 @@ impl Evaluation<Reward> for Value {
         }
     }
 
-    fn weight(self, min: &Value, rank: usize, is_bot_new: u32, visits: u32) -> i64 {
-        if is_bot_new == 1 {
-            let e = (self.value - min.value) as i64 + 10;
-            return e * e / (rank * rank + 1) as i64;
-        }
-        if is_bot_new == 2 {
-            let e = (self.value - min.value) as i64 + 10;
-            return e * e / (rank * rank + 1) as i64;
-        }
+    fn advantage(&self, worst: &Value) -> i64 {
+        (self.value - worst.value) as i64
+    }
 
-        1000 // Default return value if none of the conditions are met
+    fn score(&self) -> i64 {
+        self.value as i64
     }
 
     fn improve(&mut self, new_result: Self) {
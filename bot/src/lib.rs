@@ -1,9 +1,12 @@
+use std::time::Duration;
+
 pub use opening_book::{Book, MemoryBook};
 use serde::{Deserialize, Serialize};
 
 mod dag;
 pub mod evaluation;
 mod modes;
+pub mod selection;
 
 #[cfg(not(target_arch = "wasm32"))]
 mod desktop;
@@ -16,20 +19,36 @@ use libtetris::*;
 #[cfg(target_arch = "wasm32")]
 pub use web::Interface;
 
-pub use crate::modes::normal::{BotState, ThinkResult, Thinker};
+pub use crate::modes::normal::{AnalysisLine, BotState, ThinkResult, Thinker};
 pub use crate::modes::pcloop::PcPriority;
+pub use crate::selection::Selection;
 
 #[derive(Copy, Clone, Debug, Serialize, Deserialize)]
 #[serde(default)]
 pub struct Options {
     pub mode: MovementMode,
     pub spawn_rule: SpawnRule,
+    pub rotation_system: RotationSystem,
+    pub flip_kicks: Option<FlipKicks>,
+    pub movement_costs: MovementCosts,
     pub use_hold: bool,
     pub speculate: bool,
     pub pcloop: Option<modes::pcloop::PcPriority>,
     pub min_nodes: u32,
     pub max_nodes: u32,
+    /// How long the bot thinks after a move is requested before answering, unless it runs out of
+    /// nodes to think about.
+    pub min_think_time: Duration,
+    /// How long the bot may think after a move is requested before it has to answer. The bot
+    /// answers early if its best move stays the same while the tree doubles in size. If `None`,
+    /// the bot answers as soon as it can.
+    pub max_think_time: Option<Duration>,
     pub threads: u32,
+    pub selection: Selection,
+    /// The temperature of `Selection::Softmax`, in units of evaluation.
+    pub softmax_temperature: f64,
+    /// The exploration constant of `Selection::Uct` and `Selection::Puct`.
+    pub exploration: f64,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -41,9 +60,11 @@ enum BotMsg {
         combo: u32,
     },
     NewPiece(Piece),
-    SuggestMove(u32),
+    SuggestMove(u32, Option<Duration>),
     PlayMove(FallingPiece),
     ForceAnalysisLine(Vec<FallingPiece>),
+    RequestAnalysis(usize),
+    SetStatsInterval(Option<Duration>),
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize, Eq, PartialEq, Hash)]
@@ -63,6 +84,23 @@ impl Info {
     }
 }
 
+/// A snapshot of a running search, sent periodically once enabled with
+/// `Interface::enable_stats`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SearchStats {
+    pub nodes: u32,
+    pub nodes_per_second: f64,
+    pub depth: u32,
+    pub best_move: Option<FallingPiece>,
+    /// The evaluation of the best move, as given by `Evaluation::score`.
+    pub evaluation: Option<i64>,
+    /// The number of thinks running on the thread pool, at most `threads`.
+    pub outstanding_thinks: u32,
+    pub threads: u32,
+    /// The memory allocated by the tree's arenas, in bytes.
+    pub arena_bytes: usize,
+}
+
 #[derive(Serialize, Deserialize)]
 pub enum BotPollState {
     Waiting,
@@ -74,12 +112,20 @@ impl Default for Options {
         Options {
             mode: MovementMode::ZeroG,
             spawn_rule: SpawnRule::Row19Or20,
+            rotation_system: RotationSystem::Srs,
+            flip_kicks: None,
+            movement_costs: MovementCosts::default(),
             use_hold: true,
             speculate: true,
             pcloop: None,
             min_nodes: 0,
             max_nodes: 4_000_000_000,
+            min_think_time: Duration::from_secs(0),
+            max_think_time: None,
             threads: 1,
+            selection: Selection::RankSquared,
+            softmax_temperature: 100.0,
+            exploration: std::f64::consts::SQRT_2,
         }
     }
 }
@@ -1,10 +1,12 @@
+use std::time::Duration;
+
 use arrayvec::ArrayVec;
 use libtetris::*;
 use opening_book::Book;
 use serde::{Deserialize, Serialize};
 
 use crate::evaluation::Evaluator;
-use crate::{BotMsg, Info, Move, Options};
+use crate::{AnalysisLine, BotMsg, Info, Move, Options, SearchStats};
 
 pub mod normal;
 #[cfg(not(target_arch = "wasm32"))]
@@ -31,14 +33,36 @@ pub(crate) struct ModeSwitchedBot<'a, E: Evaluator> {
     mode: Mode<E>,
     options: Options,
     board: Board,
-    do_move: Option<u32>,
+    do_move: Option<MoveRequest>,
+    do_analysis: Option<usize>,
+    /// When stats were last taken, and the number of nodes searched then.
+    last_stats: Option<(Timestamp, u64)>,
     book: Option<&'a Book>,
 }
 
+struct MoveRequest {
+    incoming: u32,
+    requested: Timestamp,
+    /// Overrides `Options::max_think_time`.
+    think_time: Option<Duration>,
+    /// The best move and the number of nodes in the tree when it became the best move.
+    best: Option<(FallingPiece, u32)>,
+}
+
+/// A point in time for measuring think time. `Instant` isn't available on the web, so the time
+/// is taken from javascript there.
+#[derive(Copy, Clone)]
+struct Timestamp {
+    #[cfg(not(target_arch = "wasm32"))]
+    instant: std::time::Instant,
+    #[cfg(target_arch = "wasm32")]
+    millis: f64,
+}
+
 impl<'a, E: Evaluator> ModeSwitchedBot<'a, E> {
     pub fn new(board: Board, options: Options, book: Option<&'a Book>) -> Self {
         #[cfg(target_arch = "wasm32")]
-        let mode = Mode::Normal(normal::BotState::new(board.clone(), options,0));
+        let mode = Mode::Normal(normal::BotState::new(board.clone(), options));
         #[cfg(not(target_arch = "wasm32"))]
         let mode = if options.pcloop.is_some()
             && board.get_row(0).is_empty()
@@ -48,16 +72,19 @@ impl<'a, E: Evaluator> ModeSwitchedBot<'a, E> {
                 board.clone(),
                 options.use_hold,
                 options.mode,
+                options.movement_costs,
                 options.pcloop.unwrap(),
             ))
         } else {
-            Mode::Normal(normal::BotState::new(board.clone(), options,0))
+            Mode::Normal(normal::BotState::new(board.clone(), options))
         };
         ModeSwitchedBot {
             mode,
             options,
             board,
             do_move: None,
+            do_analysis: None,
+            last_stats: None,
             book,
         }
     }
@@ -79,13 +106,13 @@ impl<'a, E: Evaluator> ModeSwitchedBot<'a, E> {
         match msg {
             BotMsg::Reset { field, b2b, combo } => {
                 self.board.set_field(field);
-                self.board.b2b_bonus = b2b;
+                self.board.set_b2b_bonus(b2b);
                 self.board.combo = combo;
                 match &mut self.mode {
                     Mode::Normal(bot) => bot.reset(field, b2b, combo),
                     Mode::PcLoop(_) => {
                         self.mode =
-                            Mode::Normal(normal::BotState::new(self.board.clone(), self.options,0))
+                            Mode::Normal(normal::BotState::new(self.board.clone(), self.options))
                     }
                 }
             }
@@ -102,6 +129,7 @@ impl<'a, E: Evaluator> ModeSwitchedBot<'a, E> {
                                     self.board.clone(),
                                     self.options.use_hold,
                                     self.options.mode,
+                                    self.options.movement_costs,
                                     self.options.pcloop.unwrap(),
                                 ));
                             } else {
@@ -116,7 +144,14 @@ impl<'a, E: Evaluator> ModeSwitchedBot<'a, E> {
                     Mode::PcLoop(bot) => bot.add_next_piece(piece),
                 }
             }
-            BotMsg::SuggestMove(incoming) => self.do_move = Some(incoming),
+            BotMsg::SuggestMove(incoming, think_time) => {
+                self.do_move = Some(MoveRequest {
+                    incoming,
+                    requested: Timestamp::now(),
+                    think_time,
+                    best: None,
+                })
+            }
             BotMsg::PlayMove(mv) => {
                 let next = self.board.advance_queue().unwrap();
                 if mv.kind.0 != next {
@@ -136,6 +171,7 @@ impl<'a, E: Evaluator> ModeSwitchedBot<'a, E> {
                                     self.board.clone(),
                                     self.options.use_hold,
                                     self.options.mode,
+                                    self.options.movement_costs,
                                     self.options.pcloop.unwrap(),
                                 ));
                                 return;
@@ -145,12 +181,15 @@ impl<'a, E: Evaluator> ModeSwitchedBot<'a, E> {
                     }
                     Mode::PcLoop(bot) => {
                         if !bot.play_move(mv) {
-                            let bot = normal::BotState::new(self.board.clone(), self.options,0);
+                            let bot = normal::BotState::new(self.board.clone(), self.options);
                             self.mode = Mode::Normal(bot);
                         }
                     }
                 }
             }
+            BotMsg::RequestAnalysis(count) => self.do_analysis = Some(count),
+            // handled by the thread running the bot, since stats are sent on a timer
+            BotMsg::SetStatsInterval(_) => {}
             BotMsg::ForceAnalysisLine(path) => match &mut self.mode {
                 Mode::Normal(bot) => bot.force_analysis_line(path),
                 _ => {}
@@ -158,13 +197,27 @@ impl<'a, E: Evaluator> ModeSwitchedBot<'a, E> {
         }
     }
 
-    pub fn think(&mut self, eval: &E, send_move: impl FnOnce((Move, Info))) -> Vec<Task> {
+    pub fn think(
+        &mut self,
+        eval: &E,
+        send_move: impl FnOnce((Move, Info)),
+        send_analysis: impl FnOnce(Vec<AnalysisLine>),
+    ) -> Vec<Task> {
+        if let Some(count) = self.do_analysis.take() {
+            send_analysis(match &self.mode {
+                Mode::Normal(bot) => bot.analysis(count),
+                Mode::PcLoop(_) => vec![],
+            });
+        }
+
         match &mut self.mode {
             Mode::Normal(bot) => {
-                if let Some(incoming) = self.do_move {
-                    if let Some(result) = bot.suggest_move(eval, self.book, incoming) {
-                        send_move(result);
-                        self.do_move = None;
+                if let Some(request) = &mut self.do_move {
+                    if request.ready(bot, &self.options) {
+                        if let Some(result) = bot.suggest_move(eval, self.book, request.incoming) {
+                            send_move(result);
+                            self.do_move = None;
+                        }
                     }
                 }
 
@@ -192,7 +245,7 @@ impl<'a, E: Evaluator> ModeSwitchedBot<'a, E> {
                         }
                         Err(false) => {}
                         Err(true) => {
-                            let mut bot = normal::BotState::new(self.board.clone(), self.options,0);
+                            let mut bot = normal::BotState::new(self.board.clone(), self.options);
                             let mut thinks = vec![];
                             if let Ok(thinker) = bot.think() {
                                 thinks.push(Task::NormalThink(thinker));
@@ -208,6 +261,49 @@ impl<'a, E: Evaluator> ModeSwitchedBot<'a, E> {
         }
     }
 
+    /// A snapshot of the search, or `None` in PC loop mode.
+    pub fn stats(&mut self) -> Option<SearchStats> {
+        let bot = match &self.mode {
+            Mode::Normal(bot) => bot,
+            Mode::PcLoop(_) => return None,
+        };
+        let searched = bot.nodes_searched();
+        let nodes_per_second = match self.last_stats {
+            Some((time, last)) => {
+                searched.saturating_sub(last) as f64 / time.elapsed().as_secs_f64().max(1e-6)
+            }
+            None => 0.0,
+        };
+        self.last_stats = Some((Timestamp::now(), searched));
+        Some(SearchStats {
+            nodes: bot.nodes(),
+            nodes_per_second,
+            depth: bot.depth(),
+            best_move: bot.best_move(),
+            evaluation: bot.best_evaluation(),
+            outstanding_thinks: bot.outstanding_thinks,
+            threads: self.options.threads,
+            arena_bytes: bot.arena_bytes(),
+        })
+    }
+
+    /// Whether `interval` has passed since stats were last taken. The web worker has no timers, so
+    /// it checks this whenever it wakes up instead.
+    #[cfg(target_arch = "wasm32")]
+    pub fn stats_due(&self, interval: Duration) -> bool {
+        self.last_stats
+            .map_or(true, |(time, _)| time.elapsed() >= interval)
+    }
+
+    /// How long until the pending move request reaches its minimum or maximum think time, if it
+    /// is waiting on either. `think` needs to be called again then, even if nothing else happens.
+    pub fn move_deadline(&self) -> Option<Duration> {
+        match (&self.mode, &self.do_move) {
+            (Mode::Normal(_), Some(request)) => request.deadline(&self.options),
+            _ => None,
+        }
+    }
+
     pub fn is_dead(&self) -> bool {
         if let Mode::Normal(bot) = &self.mode {
             bot.is_dead()
@@ -217,6 +313,71 @@ impl<'a, E: Evaluator> ModeSwitchedBot<'a, E> {
     }
 }
 
+impl MoveRequest {
+    fn deadline(&self, options: &Options) -> Option<Duration> {
+        let elapsed = self.requested.elapsed();
+        std::iter::once(options.min_think_time)
+            .chain(self.think_time.or(options.max_think_time))
+            .filter(|&time| time > elapsed)
+            .min()
+            .map(|time| time - elapsed)
+    }
+
+    fn ready<E: Evaluator>(&mut self, bot: &normal::BotState<E>, options: &Options) -> bool {
+        if bot.thinking_exhausted() {
+            return true;
+        }
+        let elapsed = self.requested.elapsed();
+        if elapsed < options.min_think_time {
+            return false;
+        }
+        let think_time = match self.think_time.or(options.max_think_time) {
+            Some(think_time) => think_time,
+            None => return true,
+        };
+        if elapsed >= think_time {
+            return true;
+        }
+
+        // Stop early if the best move has stayed the same while the tree doubled in size.
+        let best = bot.best_move();
+        let nodes = bot.nodes();
+        match self.best {
+            Some((mv, since)) if best == Some(mv) => nodes >= since.saturating_mul(2),
+            _ => {
+                self.best = best.map(|mv| (mv, nodes));
+                false
+            }
+        }
+    }
+}
+
+impl Timestamp {
+    #[cfg(not(target_arch = "wasm32"))]
+    fn now() -> Self {
+        Timestamp {
+            instant: std::time::Instant::now(),
+        }
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    fn now() -> Self {
+        Timestamp {
+            millis: js_sys::Date::now(),
+        }
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn elapsed(self) -> Duration {
+        self.instant.elapsed()
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    fn elapsed(self) -> Duration {
+        Duration::from_secs_f64((js_sys::Date::now() - self.millis).max(0.0) / 1000.0)
+    }
+}
+
 impl Task {
     pub fn execute<E: Evaluator>(self, eval: &E) -> TaskResult<E::Value, E::Reward> {
         match self {
@@ -4,8 +4,8 @@ use opening_book::Book;
 use serde::{Deserialize, Serialize};
 
 // use crate::tree::{ ChildData, TreeState, NodeId };
-use crate::dag::{ChildData, DagState, NodeId};
-use crate::evaluation::Evaluator;
+use crate::dag::{ChildData, DagState, MoveCandidate, NodeId};
+use crate::evaluation::{Evaluation, Evaluator};
 use crate::Options;
 
 pub struct BotState<E: Evaluator> {
@@ -13,7 +13,7 @@ pub struct BotState<E: Evaluator> {
     options: Options,
     forced_analysis_lines: Vec<Vec<FallingPiece>>,
     pub outstanding_thinks: u32,
-    pub is_bot_new:u32,
+    nodes_searched: u64,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -26,18 +26,22 @@ pub struct Thinker {
 #[derive(Serialize, Deserialize)]
 pub enum ThinkResult<V, R> {
     Known(NodeId, Vec<ChildData<V, R>>),
-    Speculated(NodeId, EnumMap<Piece, Option<Vec<ChildData<V, R>>>>),
+    Speculated(
+        NodeId,
+        EnumMap<Piece, Option<Vec<ChildData<V, R>>>>,
+        EnumMap<Piece, u32>,
+    ),
     Unmark(NodeId),
 }
 
 impl<E: Evaluator> BotState<E> {
-    pub fn new(board: Board, options: Options,is_bot_new:u32,) -> Self {
+    pub fn new(board: Board, options: Options) -> Self {
         BotState {
-            tree: DagState::new(board, options.use_hold,is_bot_new),
+            tree: DagState::new(board, options.use_hold, options.selection.policy(&options)),
             options,
             forced_analysis_lines: vec![],
             outstanding_thinks: 0,
-            is_bot_new,
+            nodes_searched: 0,
         }
     }
 
@@ -68,11 +72,15 @@ impl<E: Evaluator> BotState<E> {
 
     pub fn finish_thinking(&mut self, result: ThinkResult<E::Value, E::Reward>) {
         self.outstanding_thinks -= 1;
+        let nodes = self.tree.nodes();
         match result {
             ThinkResult::Known(node, children) => self.tree.update_known(node, children),
-            ThinkResult::Speculated(node, children) => self.tree.update_speculated(node, children),
+            ThinkResult::Speculated(node, children, weights) => {
+                self.tree.update_speculated(node, children, weights)
+            }
             ThinkResult::Unmark(node) => self.tree.unmark(node),
         }
+        self.nodes_searched += self.tree.nodes().saturating_sub(nodes) as u64;
     }
 
     pub fn is_dead(&self) -> bool {
@@ -104,6 +112,39 @@ impl<E: Evaluator> BotState<E> {
         }
     }
 
+    /// Whether thinking more can't change the move, because the tree is dead or has reached the
+    /// node limit.
+    pub fn thinking_exhausted(&self) -> bool {
+        (self.min_thinking_reached() && self.tree.nodes() >= self.options.max_nodes)
+            || self.tree.is_dead()
+    }
+
+    pub fn nodes(&self) -> u32 {
+        self.tree.nodes()
+    }
+
+    /// The number of nodes added to the tree, including ones discarded since.
+    pub fn nodes_searched(&self) -> u64 {
+        self.nodes_searched
+    }
+
+    pub fn depth(&self) -> u32 {
+        self.tree.depth()
+    }
+
+    pub fn arena_bytes(&self) -> usize {
+        self.tree.arena_bytes()
+    }
+
+    pub fn best_move(&self) -> Option<FallingPiece> {
+        self.tree.best_move()
+    }
+
+    /// The score of the best move's evaluation, as given by `Evaluation::score`.
+    pub fn best_evaluation(&self) -> Option<i64> {
+        self.tree.best_evaluation().map(|eval| eval.score())
+    }
+
     pub fn min_thinking_reached(&self) -> bool {
         self.tree.nodes() > self.options.min_nodes
             && self.forced_analysis_lines.is_empty()
@@ -169,25 +210,50 @@ impl<E: Evaluator> BotState<E> {
             })
         };
 
+        return Some((self.make_move(&child), info));
+    }
+
+    /// The top `count` candidate moves, best first, without choosing one.
+    pub fn analysis(&self, count: usize) -> Vec<AnalysisLine> {
+        self.tree
+            .get_next_candidates()
+            .into_iter()
+            .take(count)
+            .map(|candidate| AnalysisLine {
+                mv: self.make_move(&candidate),
+                evaluation: candidate.evaluation.score(),
+                visits: candidate.visits,
+                original_rank: candidate.original_rank,
+                plan: self.tree.get_plan_after(&candidate),
+            })
+            .collect()
+    }
+
+    fn make_move(&self, candidate: &MoveCandidate<E::Value>) -> Move {
         let inputs = find_moves(
             self.tree.board(),
             self.options
                 .spawn_rule
-                .spawn(child.mv.kind.0, self.tree.board())
+                .spawn(
+                    candidate.mv.kind.0,
+                    self.tree.board(),
+                    self.options.rotation_system,
+                )
                 .unwrap(),
             self.options.mode,
+            self.options.rotation_system,
+            self.options.flip_kicks,
+            self.options.movement_costs,
         )
         .into_iter()
-        .find(|p| p.location == child.mv)
+        .find(|p| p.location == candidate.mv)
         .unwrap()
         .inputs;
-        let mv = Move {
-            hold: child.hold,
+        Move {
+            hold: candidate.hold,
             inputs: inputs.movements,
-            expected_location: child.mv,
-        };
-
-        return Some((mv, info));
+            expected_location: candidate.mv,
+        }
     }
 
     pub fn advance_move(&mut self, mv: FallingPiece) {
@@ -210,7 +276,8 @@ impl Thinker {
                     b.add_next_piece(p);
                     children[p] = Some(self.make_children(b, eval));
                 }
-                ThinkResult::Speculated(self.node, children)
+                let weights = self.board.randomizer.weights();
+                ThinkResult::Speculated(self.node, children, weights)
             } else {
                 ThinkResult::Unmark(self.node)
             }
@@ -232,7 +299,9 @@ impl Thinker {
                         b.add_next_piece(p);
                         children[p] = Some(self.make_children(b, eval));
                     }
-                    ThinkResult::Speculated(self.node, children)
+                    // the unknown piece is still the next one the randomizer deals
+                    let weights = self.board.randomizer.weights();
+                    ThinkResult::Speculated(self.node, children, weights)
                 } else {
                     ThinkResult::Unmark(self.node)
                 }
@@ -252,10 +321,15 @@ impl Thinker {
         let mut children = vec![];
 
         let next = board.advance_queue().unwrap();
-        let spawned = match self.options.spawn_rule.spawn(next, &board) {
-            Some(spawned) => spawned,
-            None => return children,
-        };
+        let spawned =
+            match self
+                .options
+                .spawn_rule
+                .spawn(next, &board, self.options.rotation_system)
+            {
+                Some(spawned) => spawned,
+                None => return children,
+            };
 
         self.add_children(&mut children, &board, eval, spawned, false);
 
@@ -266,10 +340,15 @@ impl Thinker {
             if hold == next {
                 return children;
             }
-            let spawned = match self.options.spawn_rule.spawn(hold, &board) {
-                Some(spawned) => spawned,
-                None => return children,
-            };
+            let spawned =
+                match self
+                    .options
+                    .spawn_rule
+                    .spawn(hold, &board, self.options.rotation_system)
+                {
+                    Some(spawned) => spawned,
+                    None => return children,
+                };
 
             self.add_children(&mut children, &board, eval, spawned, true);
         }
@@ -285,15 +364,27 @@ impl Thinker {
         spawned: FallingPiece,
         hold: bool,
     ) {
-        for mv in find_moves(&board, spawned, self.options.mode) {
+        for mv in find_moves(
+            &board,
+            spawned,
+            self.options.mode,
+            self.options.rotation_system,
+            self.options.flip_kicks,
+            self.options.movement_costs,
+        ) {
             let can_be_hd =
                 board.above_stack(&mv.location) && board.column_heights().iter().all(|&y| y < 18);
             let mut result = board.clone();
             let lock = result.lock_piece(mv.location);
-            // Don't add deaths by lock out, don't add useless mini tspins
-            if !lock.locked_out && !(can_be_hd && lock.placement_kind == PlacementKind::MiniTspin) {
+            // Don't add deaths by lock out, don't add useless mini spins
+            let useless_mini = match lock.placement_kind {
+                PlacementKind::MiniTspin | PlacementKind::MiniSpin => true,
+                _ => false,
+            };
+            if !lock.locked_out && !(can_be_hd && useless_mini) {
                 let move_time = mv.inputs.time + if hold { 1 } else { 0 };
-                let (evaluation, reward) = eval.evaluate(&lock, &result, move_time, spawned.kind.0);
+                let (evaluation, reward) =
+                    eval.evaluate(&lock, &mut result, move_time, spawned.kind.0);
                 children.push(ChildData {
                     evaluation,
                     reward,
@@ -312,3 +403,66 @@ pub struct Info {
     pub original_rank: u32,
     pub plan: Vec<(FallingPiece, LockResult)>,
 }
+
+/// A candidate move reported by `Interface::request_analysis`.
+#[derive(Clone, Debug, Serialize, Deserialize, Eq, PartialEq, Hash)]
+pub struct AnalysisLine {
+    pub mv: Move,
+    pub evaluation: i64,
+    /// The number of expansions of the move's node or its descendants.
+    pub visits: u32,
+    pub original_rank: u32,
+    /// The best line of play starting with the move.
+    pub plan: Vec<(FallingPiece, LockResult)>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::evaluation::Standard;
+
+    fn think_and_move(board: &str) -> (Move, crate::Info) {
+        let eval = Standard::default();
+        let mut bot = BotState::new(board.parse().unwrap(), Options::default());
+        for _ in 0..500 {
+            match bot.think() {
+                Ok(thinker) => bot.finish_thinking(thinker.think(&eval)),
+                Err(_) => break,
+            }
+        }
+        bot.suggest_move(&eval, None, 0).unwrap()
+    }
+
+    #[test]
+    fn takes_the_tetris() {
+        let (mv, info) = think_and_move(
+            "
+            queue: IOTLJSZ
+            #########.
+            #########.
+            #########.
+            #########.
+        ",
+        );
+        assert!(!mv.hold);
+        assert!(mv.expected_location.cells().iter().all(|&(x, _)| x == 9));
+        assert_eq!(info.plan()[0].1.placement_kind, PlacementKind::Clear4);
+    }
+
+    #[test]
+    fn holds_into_the_tetris() {
+        let (mv, _) = think_and_move(
+            "
+            hold: I
+            queue: SZSZ
+            #########.
+            #########.
+            #########.
+            #########.
+        ",
+        );
+        assert!(mv.hold);
+        assert_eq!(mv.expected_location.kind.0, Piece::I);
+        assert!(mv.expected_location.cells().iter().all(|&(x, _)| x == 9));
+    }
+}
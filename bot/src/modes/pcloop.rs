@@ -4,7 +4,9 @@ use std::sync::Arc;
 
 use arrayvec::ArrayVec;
 use crossbeam_channel::{unbounded, Sender};
-use libtetris::{Board, FallingPiece, LockResult, MovementMode, Piece};
+use libtetris::{
+    Board, FallingPiece, LockResult, MovementCosts, MovementMode, Piece, RotationSystem,
+};
 use serde::{Deserialize, Serialize};
 
 use crate::Move;
@@ -13,6 +15,7 @@ pub struct PcLooper {
     current_pc: VecDeque<(Move, LockResult)>,
     abort: Arc<AtomicBool>,
     mode: MovementMode,
+    costs: MovementCosts,
     next_pc_queue: VecDeque<Piece>,
     next_pc_hold: Option<Piece>,
     hold_enabled: bool,
@@ -28,7 +31,13 @@ pub struct PcSolver {
 }
 
 impl PcLooper {
-    pub fn new(board: Board, hold_enabled: bool, mode: MovementMode, priority: PcPriority) -> Self {
+    pub fn new(
+        board: Board,
+        hold_enabled: bool,
+        mode: MovementMode,
+        costs: MovementCosts,
+        priority: PcPriority,
+    ) -> Self {
         PcLooper {
             current_pc: VecDeque::new(),
             abort: Arc::new(AtomicBool::new(false)),
@@ -37,6 +46,7 @@ impl PcLooper {
             hold_enabled,
             solving: false,
             mode,
+            costs,
             priority,
         }
     }
@@ -82,9 +92,13 @@ impl PcLooper {
                 let placements = libtetris::find_moves(
                     &b,
                     libtetris::SpawnRule::Row19Or20
-                        .spawn(placement.kind.0, &b)
+                        .spawn(placement.kind.0, &b, RotationSystem::Srs)
                         .unwrap(),
                     self.mode,
+                    // PCF solves with SRS kicks.
+                    RotationSystem::Srs,
+                    None,
+                    self.costs,
                 );
 
                 let mut mv = None;
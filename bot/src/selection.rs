@@ -0,0 +1,180 @@
+//! Policies for choosing which child the tree search descends into when looking for a leaf to
+//! expand.
+
+use std::sync::Arc;
+
+use rand::distributions::WeightedIndex;
+use rand::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::Options;
+
+/// Selects a `SelectionPolicy` through `Options`.
+#[derive(Copy, Clone, Debug, Hash, Eq, PartialEq, Serialize, Deserialize)]
+pub enum Selection {
+    RankSquared,
+    Uniform,
+    Uct,
+    Puct,
+    Softmax,
+}
+
+/// What the tree search knows about a child when choosing which one to descend into.
+#[derive(Copy, Clone, Debug)]
+pub struct Candidate {
+    /// The child's position among its siblings, best first.
+    pub rank: usize,
+    /// How much better the child is evaluated than the worst of its siblings.
+    pub advantage: i64,
+    /// The number of expansions of the child or its descendants.
+    pub visits: u32,
+    /// The number of those expansions that improved the child's evaluation.
+    pub wins: u32,
+}
+
+pub trait SelectionPolicy: Send + Sync {
+    /// Picks the index of the candidate to descend into. Children that lead to death aren't
+    /// candidates, and there is always at least one candidate.
+    fn choose(&self, candidates: &[Candidate]) -> Option<usize>;
+}
+
+/// Samples children weighted by the square of their advantage, divided by the square of their
+/// rank. This is the policy Cold Clear has always used.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct RankSquared;
+
+/// Samples children uniformly, ignoring evaluations.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct Uniform;
+
+/// Picks the child with the best upper confidence bound. A child's value is its advantage as a
+/// fraction of the best advantage. Evaluations are already backed up from the best descendants, so
+/// visits only enter through the exploration term.
+#[derive(Copy, Clone, Debug)]
+pub struct Uct {
+    pub exploration: f64,
+}
+
+/// Picks children like `Uct`, but scales the exploration term of each child by its
+/// `RankSquared` weight, as AlphaZero does with its policy network.
+#[derive(Copy, Clone, Debug)]
+pub struct Puct {
+    pub exploration: f64,
+}
+
+/// Samples children weighted by `exp(advantage / temperature)`.
+#[derive(Copy, Clone, Debug)]
+pub struct Softmax {
+    pub temperature: f64,
+}
+
+impl Selection {
+    pub(crate) fn policy(self, options: &Options) -> Arc<dyn SelectionPolicy> {
+        match self {
+            Selection::RankSquared => Arc::new(RankSquared),
+            Selection::Uniform => Arc::new(Uniform),
+            Selection::Uct => Arc::new(Uct {
+                exploration: options.exploration,
+            }),
+            Selection::Puct => Arc::new(Puct {
+                exploration: options.exploration,
+            }),
+            Selection::Softmax => Arc::new(Softmax {
+                temperature: options.softmax_temperature,
+            }),
+        }
+    }
+}
+
+impl Default for Selection {
+    fn default() -> Self {
+        Selection::RankSquared
+    }
+}
+
+impl RankSquared {
+    fn weight(candidate: &Candidate) -> i64 {
+        let e = candidate.advantage + 10;
+        e * e / (candidate.rank * candidate.rank + 1) as i64
+    }
+}
+
+impl SelectionPolicy for RankSquared {
+    fn choose(&self, candidates: &[Candidate]) -> Option<usize> {
+        let sampler = WeightedIndex::new(candidates.iter().map(RankSquared::weight)).ok()?;
+        Some(thread_rng().sample(sampler))
+    }
+}
+
+impl SelectionPolicy for Uniform {
+    fn choose(&self, candidates: &[Candidate]) -> Option<usize> {
+        Some(thread_rng().gen_range(0, candidates.len()))
+    }
+}
+
+/// The value of each candidate, between 0 and 1, and the total visits of the candidates.
+fn values(candidates: &[Candidate]) -> (impl Iterator<Item = f64> + '_, f64) {
+    let best = candidates
+        .iter()
+        .map(|c| c.advantage)
+        .max()
+        .unwrap_or(0)
+        .max(1) as f64;
+    let visits = candidates.iter().map(|c| c.visits as f64).sum();
+    (
+        candidates.iter().map(move |c| c.advantage as f64 / best),
+        visits,
+    )
+}
+
+/// The index of the largest score, preferring better ranked candidates on ties.
+fn best(scores: impl Iterator<Item = f64>) -> Option<usize> {
+    let mut best = None;
+    for (i, score) in scores.enumerate() {
+        if best.map_or(true, |(_, s)| score > s) {
+            best = Some((i, score));
+        }
+    }
+    best.map(|(i, _)| i)
+}
+
+impl SelectionPolicy for Uct {
+    fn choose(&self, candidates: &[Candidate]) -> Option<usize> {
+        let (values, visits) = values(candidates);
+        let ln_visits = (visits + 1.0).ln();
+        best(values.zip(candidates).map(|(value, c)| {
+            value + self.exploration * (ln_visits / (c.visits as f64 + 1.0)).sqrt()
+        }))
+    }
+}
+
+impl SelectionPolicy for Puct {
+    fn choose(&self, candidates: &[Candidate]) -> Option<usize> {
+        let total_weight = candidates
+            .iter()
+            .map(RankSquared::weight)
+            .sum::<i64>()
+            .max(1) as f64;
+        let (values, visits) = values(candidates);
+        let sqrt_visits = (visits + 1.0).sqrt();
+        best(values.zip(candidates).map(|(value, c)| {
+            let prior = RankSquared::weight(c) as f64 / total_weight;
+            value + self.exploration * prior * sqrt_visits / (c.visits as f64 + 1.0)
+        }))
+    }
+}
+
+impl SelectionPolicy for Softmax {
+    fn choose(&self, candidates: &[Candidate]) -> Option<usize> {
+        // Advantages are relative to the worst candidate, so shift them to be relative to the
+        // best instead to keep the exponentials from overflowing.
+        let best = candidates.iter().map(|c| c.advantage).max()?;
+        let sampler = WeightedIndex::new(
+            candidates
+                .iter()
+                .map(|c| ((c.advantage - best) as f64 / self.temperature).exp()),
+        )
+        .ok()?;
+        Some(thread_rng().sample(sampler))
+    }
+}
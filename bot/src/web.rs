@@ -1,14 +1,17 @@
+use std::collections::VecDeque;
+use std::time::Duration;
+
 use futures_util::{pin_mut, select, FutureExt};
 use libtetris::*;
 use serde::de::DeserializeOwned;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use webutil::channel::{channel, Receiver};
 use webutil::prelude::*;
 use webutil::worker::{Worker, WorkerSender};
 
 use crate::evaluation::Evaluator;
 use crate::modes::{ModeSwitchedBot, Task, TaskResult};
-use crate::{BotMsg, BotPollState, Info, Options};
+use crate::{AnalysisLine, BotMsg, BotPollState, Info, Options, SearchStats};
 
 // trait aliases (#41517) would make my life SOOOOO much easier
 // pub trait WebCompatibleEvaluator = where
@@ -16,7 +19,22 @@ use crate::{BotMsg, BotPollState, Info, Options};
 //     <Self as Evaluator>::Reward: Serialize + DeserializeOwned,
 //     <Self as Evaluator>::Value: Serialize + DeserializeOwned;
 
-pub struct Interface(Option<Worker<BotMsg, Option<(Move, Info)>>>);
+pub struct Interface {
+    worker: Option<Worker<BotMsg, BotResponse>>,
+    /// Responses received while waiting for a different kind of response.
+    moves: VecDeque<(Move, Info)>,
+    analysis: VecDeque<Vec<AnalysisLine>>,
+    stats: Option<SearchStats>,
+}
+
+/// The worker only has one channel back to the interface, so everything it sends goes through it.
+#[derive(Serialize, Deserialize)]
+enum BotResponse {
+    Move(Move, Info),
+    Analysis(Vec<AnalysisLine>),
+    Stats(SearchStats),
+    Dead,
+}
 
 impl Interface {
     /// Launches a bot worker with the specified starting board and options.
@@ -38,7 +56,12 @@ impl Interface {
         .await
         .unwrap();
 
-        Interface(Some(worker))
+        Interface {
+            worker: Some(worker),
+            moves: VecDeque::new(),
+            analysis: VecDeque::new(),
+            stats: None,
+        }
     }
 
     /// Request the bot to provide a move as soon as possible.
@@ -47,6 +70,10 @@ impl Interface {
     /// the provided lower limit on thinking has not been reached yet or if the bot cannot provide
     /// a move yet, usually because it lacks information on the next pieces.
     ///
+    /// If `think_time` is given, the bot instead keeps thinking for up to that long before
+    /// answering, as with `Options::max_think_time`. It answers early if its best move stops
+    /// changing.
+    ///
     /// For example, in a game with zero piece previews and hold enabled, the bot will never be able
     /// to provide the first move because it cannot know what piece it will be placing if it chooses
     /// to hold. Another example: in a game with zero piece previews and hold disabled, the bot
@@ -58,9 +85,12 @@ impl Interface {
     ///
     /// Once a move is chosen, the move will become available by calling `poll_next_move` or
     /// `block_next_move`. To update the bot state according to this move, call `play_next_move`.
-    pub fn suggest_next_move(&self, incoming: u32) {
-        if let Some(worker) = &self.0 {
-            worker.send(&BotMsg::SuggestMove(incoming)).ok().unwrap();
+    pub fn suggest_next_move(&self, incoming: u32, think_time: Option<Duration>) {
+        if let Some(worker) = &self.worker {
+            worker
+                .send(&BotMsg::SuggestMove(incoming, think_time))
+                .ok()
+                .unwrap();
         }
     }
 
@@ -73,15 +103,10 @@ impl Interface {
     /// If the piece couldn't be placed in the expected location, you must call `reset` to reset the
     /// game field, back-to-back status, and combo values.
     pub fn poll_next_move(&mut self) -> Result<(Move, Info), BotPollState> {
-        match &self.0 {
-            Some(worker) => match worker.try_recv() {
-                Some(Some(mv)) => Ok(mv),
-                Some(None) => {
-                    self.0 = None;
-                    Err(BotPollState::Dead)
-                }
-                None => Err(BotPollState::Waiting),
-            },
+        self.poll();
+        match self.moves.pop_front() {
+            Some(mv) => Ok(mv),
+            None if self.worker.is_some() => Err(BotPollState::Waiting),
             None => Err(BotPollState::Dead),
         }
     }
@@ -90,18 +115,17 @@ impl Interface {
     ///
     /// `None` is returned if the bot is dead.
     pub async fn block_next_move(&mut self) -> Option<(Move, Info)> {
-        match self.0.as_ref()?.recv().await {
-            Some(v) => Some(v),
-            None => {
-                self.0 = None;
-                None
+        loop {
+            if let Some(mv) = self.moves.pop_front() {
+                return Some(mv);
             }
+            self.wait().await?;
         }
     }
 
     /// Updates the internal bot state according to the move played.
     pub fn play_next_move(&self, mv: FallingPiece) {
-        if let Some(worker) = &self.0 {
+        if let Some(worker) = &self.worker {
             worker.send(&BotMsg::PlayMove(mv)).ok();
         }
     }
@@ -112,7 +136,7 @@ impl Interface {
     /// bag you've provided the sequence IJOZT, then the next time you call this function you can
     /// only provide either an L or an S piece.
     pub fn add_next_piece(&self, piece: Piece) {
-        if let Some(worker) = &self.0 {
+        if let Some(worker) = &self.worker {
             worker.send(&BotMsg::NewPiece(piece)).unwrap();
         }
     }
@@ -127,7 +151,7 @@ impl Interface {
     /// number of consecutive line clears achieved. So, generally speaking, if "x Combo" appears
     /// on the screen, you need to use x+1 here.
     pub fn reset(&self, field: [[bool; 10]; 40], b2b_active: bool, combo: u32) {
-        if let Some(worker) = &self.0 {
+        if let Some(worker) = &self.worker {
             worker
                 .send(&BotMsg::Reset {
                     field,
@@ -138,18 +162,85 @@ impl Interface {
         }
     }
 
+    /// Waits for the bot's current top `count` candidate moves, best first, without choosing or
+    /// playing one. The bot keeps thinking afterwards.
+    ///
+    /// The list is empty if the bot hasn't found any moves yet. `None` is returned if the bot is
+    /// dead.
+    pub async fn request_analysis(&mut self, count: usize) -> Option<Vec<AnalysisLine>> {
+        self.worker
+            .as_ref()?
+            .send(&BotMsg::RequestAnalysis(count))
+            .unwrap();
+        loop {
+            if let Some(analysis) = self.analysis.pop_front() {
+                return Some(analysis);
+            }
+            self.wait().await?;
+        }
+    }
+
+    /// Starts or stops sending a snapshot of the search every `interval`. Snapshots become
+    /// available by calling `poll_stats`.
+    ///
+    /// The bot only checks whether a snapshot is due when it wakes up to handle a message or a
+    /// finished think, so snapshots can be late while it isn't thinking.
+    pub fn enable_stats(&self, interval: Option<Duration>) {
+        if let Some(worker) = &self.worker {
+            worker.send(&BotMsg::SetStatsInterval(interval)).unwrap();
+        }
+    }
+
+    /// Returns the most recent search snapshot sent since the last call, if any.
+    ///
+    /// No snapshots are sent while the bot is in PC loop mode.
+    pub fn poll_stats(&mut self) -> Option<SearchStats> {
+        self.poll();
+        self.stats.take()
+    }
+
     /// Specifies a line that Cold Clear should analyze before making any moves.
     pub fn force_analysis_line(&self, path: Vec<FallingPiece>) {
-        if let Some(worker) = &self.0 {
+        if let Some(worker) = &self.worker {
             worker.send(&BotMsg::ForceAnalysisLine(path)).unwrap();
         }
     }
+
+    /// Receives every response the bot has sent without waiting.
+    fn poll(&mut self) {
+        loop {
+            let response = match &self.worker {
+                Some(worker) => worker.try_recv(),
+                None => return,
+            };
+            match response {
+                Some(response) => self.receive(response),
+                None => return,
+            }
+        }
+    }
+
+    /// Waits for the next response from the bot. `None` is returned if the bot is dead.
+    async fn wait(&mut self) -> Option<()> {
+        let response = self.worker.as_ref()?.recv().await;
+        self.receive(response);
+        self.worker.as_ref().map(|_| ())
+    }
+
+    fn receive(&mut self, response: BotResponse) {
+        match response {
+            BotResponse::Move(mv, info) => self.moves.push_back((mv, info)),
+            BotResponse::Analysis(analysis) => self.analysis.push_back(analysis),
+            BotResponse::Stats(stats) => self.stats = Some(stats),
+            BotResponse::Dead => self.worker = None,
+        }
+    }
 }
 
 fn bot_thread<E>(
     (board, options, eval, worker_uri): (Board, Options, E, String),
     recv: Receiver<BotMsg>,
-    send: WorkerSender<Option<(Move, Info)>>,
+    send: WorkerSender<BotResponse>,
 ) where
     E: Evaluator + Clone + Serialize + DeserializeOwned + 'static,
     E::Value: Serialize + DeserializeOwned,
@@ -178,17 +269,31 @@ fn bot_thread<E>(
         // TODO: expose opening books in web api
         // (books tend to be very large, possibly not useful?)
 
+        let mut stats_interval = None;
         loop {
-            let new_tasks = state.think(&eval, |v| send.send(&Some(v)));
+            let new_tasks = state.think(
+                &eval,
+                |(mv, info)| send.send(&BotResponse::Move(mv, info)),
+                |analysis| send.send(&BotResponse::Analysis(analysis)),
+            );
             for task in new_tasks {
                 task_send.send(task).ok().unwrap();
             }
 
+            if let Some(interval) = stats_interval {
+                if state.stats_due(interval) {
+                    if let Some(stats) = state.stats() {
+                        send.send(&BotResponse::Stats(stats));
+                    }
+                }
+            }
+
             let msg = recv.recv().fuse();
             let task = result_recv.recv().fuse();
             pin_mut!(msg, task);
             select! {
                 msg = msg => match msg {
+                    Some(BotMsg::SetStatsInterval(interval)) => stats_interval = interval,
                     Some(msg) => state.message(msg),
                     None => break
                 },
@@ -200,7 +305,7 @@ fn bot_thread<E>(
             }
         }
 
-        send.send(&None);
+        send.send(&BotResponse::Dead);
     });
 }
 
@@ -20,13 +20,17 @@ typedef enum CCMovement {
     CC_LEFT, CC_RIGHT,
     CC_CW, CC_CCW,
     /* Soft drop all the way down */
-    CC_DROP
+    CC_DROP,
+    /* 180 degree rotation */
+    CC_FLIP
 } CCMovement;
 
 typedef enum CCMovementMode {
     CC_0G,
     CC_20G,
-    CC_HARD_DROP_ONLY
+    CC_HARD_DROP_ONLY,
+    /* Hard drops, plus soft dropping and then shifting along the stack */
+    CC_HARD_DROP_AND_TUCK
 } CCMovementMode;
 
 typedef enum CCSpawnRule {
@@ -34,6 +38,51 @@ typedef enum CCSpawnRule {
     CC_ROW_21_AND_FALL,
 } CCSpawnRule;
 
+typedef enum CCRotationSystem {
+    CC_SRS,
+    /* SRS with TETR.IO's symmetric I kicks */
+    CC_SRS_PLUS,
+    CC_NO_KICKS,
+    CC_ARS,
+} CCRotationSystem;
+
+/* Kick table used for 180 degree rotations; CC_FLIP_OFF disables them */
+typedef enum CCFlipKicks {
+    CC_FLIP_OFF,
+    CC_FLIP_TETRIO,
+    CC_FLIP_JSTRIS,
+} CCFlipKicks;
+
+typedef enum CCSpinRule {
+    /* T-spins only, using the 3-corner rule */
+    CC_TSPINS_ONLY,
+    /* Non-T pieces that can't move after rotating get mini spins */
+    CC_ALL_MINI,
+    /* Non-T pieces that can't move after rotating get full spins */
+    CC_ALL_SPIN,
+} CCSpinRule;
+
+/* Garbage rules of the game being played */
+typedef enum CCAttackTable {
+    CC_ATTACK_PPT,
+    CC_ATTACK_TETRIO,
+    CC_ATTACK_JSTRIS,
+    CC_ATTACK_T99,
+} CCAttackTable;
+
+/* Randomizer used to speculate on unknown pieces */
+typedef enum CCRandomizer {
+    CC_RANDOMIZER_7_BAG,
+    CC_RANDOMIZER_14_BAG,
+    CC_RANDOMIZER_RANDOM,
+    /* TGM history randomizer with 4 rolls */
+    CC_RANDOMIZER_TGM1,
+    /* TGM history randomizer with 6 rolls */
+    CC_RANDOMIZER_TGM2,
+    /* 7-bag with an extra random piece in each bag */
+    CC_RANDOMIZER_BAG_PLUS_ONE,
+} CCRandomizer;
+
 typedef enum CCBotPollStatus {
     CC_MOVE_PROVIDED,
     CC_WAITING,
@@ -46,6 +95,15 @@ typedef enum CCPcPriority {
     CC_PC_ATTACK
 } CCPcPriority;
 
+/* How the search chooses which moves to think about */
+typedef enum CCSelection {
+    CC_SELECT_RANK_SQUARED,
+    CC_SELECT_UNIFORM,
+    CC_SELECT_UCT,
+    CC_SELECT_PUCT,
+    CC_SELECT_SOFTMAX
+} CCSelection;
+
 typedef struct CCPlanPlacement {
     CCPiece piece;
     CCTspinStatus tspin;
@@ -78,16 +136,34 @@ typedef struct CCMove {
 typedef struct CCOptions {
     CCMovementMode mode;
     CCSpawnRule spawn_rule;
+    CCRotationSystem rotation_system;
+    CCFlipKicks flip_kicks;
+    CCSpinRule spin_rule;
+    CCAttackTable attack_table;
+    CCRandomizer randomizer;
     CCPcPriority pcloop;
+    CCSelection selection;
+    /* Only used by CC_SELECT_SOFTMAX */
+    double softmax_temperature;
+    /* Only used by CC_SELECT_UCT and CC_SELECT_PUCT */
+    double exploration;
     uint32_t min_nodes;
     uint32_t max_nodes;
+    /* Time to think after a move is requested, in milliseconds. 0 max_think_time_ms is no limit. */
+    uint32_t min_think_time_ms;
+    uint32_t max_think_time_ms;
     uint32_t threads;
+    /* Handling of the game, in frames. These are used to estimate how long moves take. */
+    uint32_t delayed_auto_shift;
+    uint32_t auto_repeat_rate;
+    uint32_t soft_drop_speed;
     bool use_hold;
     bool speculate;
 } CCOptions;
 
 typedef struct CCWeights {
     int32_t back_to_back;
+    int32_t b2b_chain;
     int32_t bumpiness;
     int32_t bumpiness_sq;
     int32_t row_transitions;
@@ -107,6 +183,7 @@ typedef struct CCWeights {
     int32_t well_column[10];
 
     int32_t b2b_clear;
+    int32_t b2b_surge;
     int32_t clear1;
     int32_t clear2;
     int32_t clear3;
@@ -116,6 +193,13 @@ typedef struct CCWeights {
     int32_t tspin3;
     int32_t mini_tspin1;
     int32_t mini_tspin2;
+    int32_t mini_tspin3;
+    int32_t mini_spin1;
+    int32_t mini_spin2;
+    int32_t mini_spin3;
+    int32_t spin1;
+    int32_t spin2;
+    int32_t spin3;
     int32_t perfect_clear;
     int32_t combo_garbage;
     int32_t move_time;
@@ -147,7 +231,9 @@ CCAsyncBot *cc_launch_async(CCOptions *options, CCWeights *weights, CCBook *book
  * 
  * The bag_remain parameter is a bit field indicating which pieces are still in the bag. Each bit
  * correspond to CCPiece enum. This must match the next few pieces provided to CC via
- * cc_add_next_piece_async later.
+ * cc_add_next_piece_async later. With CC_RANDOMIZER_14_BAG each piece in it has one copy left, and
+ * with CC_RANDOMIZER_BAG_PLUS_ONE the extra piece has yet to be dealt. It is ignored by randomizers
+ * without a bag.
  * 
  * The field parameter is a pointer to the start of an array of 400 booleans in row major order,
  * with index 0 being the bottom-left cell.
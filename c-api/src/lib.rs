@@ -2,11 +2,14 @@ use std::ffi::CStr;
 use std::mem::MaybeUninit;
 use std::os::raw::c_char;
 use std::sync::Arc;
+use std::time::Duration;
 
-use cold_clear::PcPriority;
+use cold_clear::{PcPriority, Selection};
 use enumset::EnumSet;
 use libtetris::{
-    Board, FallingPiece, LockResult, MovementMode, Piece, PieceMovement, SpawnRule, TspinStatus,
+    AttackTable, BagWithExtra, Board, FallingPiece, FlipKicks, FourteenBag, History, LockResult,
+    MovementCosts, MovementMode, Piece, PieceMovement, PieceRandomizer, PureRandom, RotationSystem,
+    SevenBag, SpawnRule, SpinRule, TspinStatus,
 };
 
 type CCAsyncBot = cold_clear::Interface;
@@ -87,7 +90,8 @@ cenum! {
         CC_RIGHT => PieceMovement::Right,
         CC_CW => PieceMovement::Cw,
         CC_CCW => PieceMovement::Ccw,
-        CC_DROP => PieceMovement::SonicDrop
+        CC_DROP => PieceMovement::SonicDrop,
+        CC_FLIP => PieceMovement::Flip
     }
 
     enum CCSpawnRule => SpawnRule {
@@ -95,10 +99,30 @@ cenum! {
         CC_ROW_21_AND_FALL => SpawnRule::Row21AndFall
     }
 
+    enum CCRotationSystem => RotationSystem {
+        CC_SRS => RotationSystem::Srs,
+        CC_SRS_PLUS => RotationSystem::SrsPlus,
+        CC_NO_KICKS => RotationSystem::NoKicks,
+        CC_ARS => RotationSystem::Ars
+    }
+
+    enum CCFlipKicks => Option<FlipKicks> {
+        CC_FLIP_OFF => None,
+        CC_FLIP_TETRIO => Some(FlipKicks::Tetrio),
+        CC_FLIP_JSTRIS => Some(FlipKicks::Jstris)
+    }
+
+    enum CCSpinRule => SpinRule {
+        CC_TSPINS_ONLY => SpinRule::TSpinsOnly,
+        CC_ALL_MINI => SpinRule::AllMini,
+        CC_ALL_SPIN => SpinRule::AllSpin
+    }
+
     enum CCMovementMode => MovementMode {
         CC_0G => MovementMode::ZeroG,
         CC_20G => MovementMode::TwentyG,
-        CC_HARD_DROP_ONLY => MovementMode::HardDropOnly
+        CC_HARD_DROP_ONLY => MovementMode::HardDropOnly,
+        CC_HARD_DROP_AND_TUCK => MovementMode::HardDropAndTuck
     }
 
     enum CCPcPriority => Option<PcPriority> {
@@ -106,6 +130,60 @@ cenum! {
         CC_PC_FASTEST => Some(PcPriority::Fastest),
         CC_PC_ATTACK => Some(PcPriority::HighestAttack)
     }
+
+    enum CCSelection => Selection {
+        CC_SELECT_RANK_SQUARED => Selection::RankSquared,
+        CC_SELECT_UNIFORM => Selection::Uniform,
+        CC_SELECT_UCT => Selection::Uct,
+        CC_SELECT_PUCT => Selection::Puct,
+        CC_SELECT_SOFTMAX => Selection::Softmax
+    }
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug)]
+#[allow(non_camel_case_types)]
+enum CCAttackTable {
+    CC_ATTACK_PPT,
+    CC_ATTACK_TETRIO,
+    CC_ATTACK_JSTRIS,
+    CC_ATTACK_T99,
+}
+
+impl From<CCAttackTable> for AttackTable {
+    fn from(v: CCAttackTable) -> AttackTable {
+        match v {
+            CCAttackTable::CC_ATTACK_PPT => AttackTable::ppt(),
+            CCAttackTable::CC_ATTACK_TETRIO => AttackTable::tetrio(),
+            CCAttackTable::CC_ATTACK_JSTRIS => AttackTable::jstris(),
+            CCAttackTable::CC_ATTACK_T99 => AttackTable::tetris99(),
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug)]
+#[allow(non_camel_case_types)]
+enum CCRandomizer {
+    CC_RANDOMIZER_7_BAG,
+    CC_RANDOMIZER_14_BAG,
+    CC_RANDOMIZER_RANDOM,
+    CC_RANDOMIZER_TGM1,
+    CC_RANDOMIZER_TGM2,
+    CC_RANDOMIZER_BAG_PLUS_ONE,
+}
+
+impl From<CCRandomizer> for PieceRandomizer {
+    fn from(v: CCRandomizer) -> PieceRandomizer {
+        match v {
+            CCRandomizer::CC_RANDOMIZER_7_BAG => SevenBag::new().into(),
+            CCRandomizer::CC_RANDOMIZER_14_BAG => FourteenBag::new().into(),
+            CCRandomizer::CC_RANDOMIZER_RANDOM => PureRandom.into(),
+            CCRandomizer::CC_RANDOMIZER_TGM1 => History::tgm1().into(),
+            CCRandomizer::CC_RANDOMIZER_TGM2 => History::tgm2().into(),
+            CCRandomizer::CC_RANDOMIZER_BAG_PLUS_ONE => BagWithExtra::new().into(),
+        }
+    }
 }
 
 #[repr(C)]
@@ -144,10 +222,23 @@ struct CCPlanPlacement {
 struct CCOptions {
     mode: CCMovementMode,
     spawn_rule: CCSpawnRule,
+    rotation_system: CCRotationSystem,
+    flip_kicks: CCFlipKicks,
+    spin_rule: CCSpinRule,
+    attack_table: CCAttackTable,
+    randomizer: CCRandomizer,
     pcloop: CCPcPriority,
+    selection: CCSelection,
+    softmax_temperature: f64,
+    exploration: f64,
     min_nodes: u32,
     max_nodes: u32,
+    min_think_time_ms: u32,
+    max_think_time_ms: u32,
     threads: u32,
+    delayed_auto_shift: u32,
+    auto_repeat_rate: u32,
+    soft_drop_speed: u32,
     use_hold: bool,
     speculate: bool,
 }
@@ -155,6 +246,7 @@ struct CCOptions {
 #[repr(C)]
 struct CCWeights {
     back_to_back: i32,
+    b2b_chain: i32,
     bumpiness: i32,
     bumpiness_sq: i32,
     row_transitions: i32,
@@ -174,6 +266,7 @@ struct CCWeights {
     well_column: [i32; 10],
 
     b2b_clear: i32,
+    b2b_surge: i32,
     clear1: i32,
     clear2: i32,
     clear3: i32,
@@ -183,6 +276,13 @@ struct CCWeights {
     tspin3: i32,
     mini_tspin1: i32,
     mini_tspin2: i32,
+    mini_tspin3: i32,
+    mini_spin1: i32,
+    mini_spin2: i32,
+    mini_spin3: i32,
+    spin1: i32,
+    spin2: i32,
+    spin3: i32,
     perfect_clear: i32,
     combo_garbage: i32,
     move_time: i32,
@@ -205,18 +305,34 @@ fn convert_from_c_options(options: &CCOptions) -> cold_clear::Options {
     cold_clear::Options {
         max_nodes: options.max_nodes,
         min_nodes: options.min_nodes,
+        min_think_time: Duration::from_millis(options.min_think_time_ms as u64),
+        max_think_time: match options.max_think_time_ms {
+            0 => None,
+            ms => Some(Duration::from_millis(ms as u64)),
+        },
         use_hold: options.use_hold,
         speculate: options.speculate,
         pcloop: options.pcloop.into(),
         mode: options.mode.into(),
         spawn_rule: options.spawn_rule.into(),
+        rotation_system: options.rotation_system.into(),
+        flip_kicks: options.flip_kicks.into(),
+        movement_costs: MovementCosts {
+            delayed_auto_shift: options.delayed_auto_shift,
+            auto_repeat_rate: options.auto_repeat_rate,
+            soft_drop_speed: options.soft_drop_speed,
+        },
         threads: options.threads,
+        selection: options.selection.into(),
+        softmax_temperature: options.softmax_temperature,
+        exploration: options.exploration,
     }
 }
 
 fn convert_from_c_weights(weights: &CCWeights) -> cold_clear::evaluation::Standard {
     cold_clear::evaluation::Standard {
         back_to_back: weights.back_to_back,
+        b2b_chain: weights.b2b_chain,
         bumpiness: weights.bumpiness,
         bumpiness_sq: weights.bumpiness_sq,
         row_transitions: weights.row_transitions,
@@ -236,6 +352,7 @@ fn convert_from_c_weights(weights: &CCWeights) -> cold_clear::evaluation::Standa
         well_column: weights.well_column,
 
         b2b_clear: weights.b2b_clear,
+        b2b_surge: weights.b2b_surge,
         clear1: weights.clear1,
         clear2: weights.clear2,
         clear3: weights.clear3,
@@ -245,6 +362,13 @@ fn convert_from_c_weights(weights: &CCWeights) -> cold_clear::evaluation::Standa
         tspin3: weights.tspin3,
         mini_tspin1: weights.mini_tspin1,
         mini_tspin2: weights.mini_tspin2,
+        mini_tspin3: weights.mini_tspin3,
+        mini_spin1: weights.mini_spin1,
+        mini_spin2: weights.mini_spin2,
+        mini_spin3: weights.mini_spin3,
+        spin1: weights.spin1,
+        spin2: weights.spin2,
+        spin3: weights.spin3,
         perfect_clear: weights.perfect_clear,
         combo_garbage: weights.combo_garbage,
         move_time: weights.move_time,
@@ -277,6 +401,12 @@ unsafe extern "C" fn cc_launch_with_board_async(
         b2b,
         combo,
     );
+    board.spin_rule = options.spin_rule.into();
+    board.attack = options.attack_table.into();
+    board.randomizer = options.randomizer.into();
+    board
+        .randomizer
+        .set_bag(EnumSet::try_from_u32(bag_remain).unwrap_or_default());
     for i in 0..count as usize {
         board.add_next_piece((*pieces.add(i)).into());
     }
@@ -303,6 +433,9 @@ unsafe extern "C" fn cc_launch_async(
     count: u32,
 ) -> *mut CCAsyncBot {
     let mut board = Board::new();
+    board.spin_rule = options.spin_rule.into();
+    board.attack = options.attack_table.into();
+    board.randomizer = options.randomizer.into();
     for i in 0..count as usize {
         board.add_next_piece((*pieces.add(i)).into());
     }
@@ -344,7 +477,7 @@ extern "C" fn cc_add_next_piece_async(bot: &mut CCAsyncBot, piece: CCPiece) {
 
 #[no_mangle]
 extern "C" fn cc_request_next_move(bot: &mut CCAsyncBot, incoming: u32) {
-    bot.suggest_next_move(incoming);
+    bot.suggest_next_move(incoming, None);
 }
 
 fn convert_plan_placement(
@@ -465,18 +598,32 @@ unsafe extern "C" fn cc_default_options(options: *mut CCOptions) {
     options.write(CCOptions {
         max_nodes: o.max_nodes,
         min_nodes: o.min_nodes,
+        min_think_time_ms: o.min_think_time.as_millis() as u32,
+        max_think_time_ms: o.max_think_time.map_or(0, |t| t.as_millis() as u32),
         use_hold: o.use_hold,
         speculate: o.speculate,
         pcloop: o.pcloop.into(),
         mode: o.mode.into(),
         spawn_rule: o.spawn_rule.into(),
+        rotation_system: o.rotation_system.into(),
+        flip_kicks: o.flip_kicks.into(),
+        spin_rule: SpinRule::default().into(),
+        attack_table: CCAttackTable::CC_ATTACK_PPT,
+        randomizer: CCRandomizer::CC_RANDOMIZER_7_BAG,
         threads: o.threads,
+        selection: o.selection.into(),
+        softmax_temperature: o.softmax_temperature,
+        exploration: o.exploration,
+        delayed_auto_shift: o.movement_costs.delayed_auto_shift,
+        auto_repeat_rate: o.movement_costs.auto_repeat_rate,
+        soft_drop_speed: o.movement_costs.soft_drop_speed,
     });
 }
 
 fn convert_weights(w: cold_clear::evaluation::Standard) -> CCWeights {
     CCWeights {
         back_to_back: w.back_to_back,
+        b2b_chain: w.b2b_chain,
         bumpiness: w.bumpiness,
         bumpiness_sq: w.bumpiness_sq,
         row_transitions: w.row_transitions,
@@ -496,6 +643,7 @@ fn convert_weights(w: cold_clear::evaluation::Standard) -> CCWeights {
         well_column: w.well_column,
 
         b2b_clear: w.b2b_clear,
+        b2b_surge: w.b2b_surge,
         clear1: w.clear1,
         clear2: w.clear2,
         clear3: w.clear3,
@@ -505,6 +653,13 @@ fn convert_weights(w: cold_clear::evaluation::Standard) -> CCWeights {
         tspin3: w.tspin3,
         mini_tspin1: w.mini_tspin1,
         mini_tspin2: w.mini_tspin2,
+        mini_tspin3: w.mini_tspin3,
+        mini_spin1: w.mini_spin1,
+        mini_spin2: w.mini_spin2,
+        mini_spin3: w.mini_spin3,
+        spin1: w.spin1,
+        spin2: w.spin2,
+        spin3: w.spin3,
         perfect_clear: w.perfect_clear,
         combo_garbage: w.combo_garbage,
         move_time: w.move_time,
@@ -21,17 +21,15 @@ pub struct BotInput {
     executing: Option<(FallingPiece, PieceMoveExecutor)>,
     controller: Controller,
     speed_limit: u32,
-    is_bot_new: u32,
 }
 
 impl BotInput {
-    pub fn new(interface: cold_clear::Interface, speed_limit: u32, is_bot_new: u32) -> Self {
+    pub fn new(interface: cold_clear::Interface, speed_limit: u32) -> Self {
         BotInput {
             interface,
             executing: None,
             controller: Default::default(),
             speed_limit,
-            is_bot_new,
         }
     }
 }
@@ -52,12 +50,12 @@ impl InputSource for BotInput {
                 Event::PieceSpawned { new_in_queue } => {
                     self.interface.add_next_piece(*new_in_queue);
                     if self.executing.is_none() {
-                        self.interface.suggest_next_move(incoming);
+                        self.interface.suggest_next_move(incoming, None);
                     }
                 }
                 Event::GarbageAdded(_) => {
                     self.interface
-                        .reset(board.get_field(), board.b2b_bonus, board.combo);
+                        .reset(board.get_field(), board.b2b_bonus(), board.combo);
                 }
                 _ => {}
             }
@@ -75,7 +73,7 @@ impl InputSource for BotInput {
             if let Some(loc) = executor.update(&mut self.controller, board, events) {
                 if loc != expected {
                     self.interface
-                        .reset(board.get_field(), board.b2b_bonus, board.combo);
+                        .reset(board.get_field(), board.b2b_bonus(), board.combo);
                 }
                 self.executing = None;
             }
@@ -96,6 +94,8 @@ struct Config<T> {
     right: T,
     rotate_left: T,
     rotate_right: T,
+    #[serde(default)]
+    rotate_180: Option<T>,
     hard_drop: T,
     soft_drop: T,
     hold: T,
@@ -108,6 +108,7 @@ impl Default for Config<VirtualKeyCode> {
             right: VirtualKeyCode::Right,
             rotate_left: VirtualKeyCode::Z,
             rotate_right: VirtualKeyCode::X,
+            rotate_180: Some(VirtualKeyCode::A),
             hard_drop: VirtualKeyCode::Space,
             soft_drop: VirtualKeyCode::Down,
             hold: VirtualKeyCode::C,
@@ -122,6 +123,7 @@ impl Default for Config<GamepadControl> {
             right: GamepadControl::Button(Button::DPadRight),
             rotate_left: GamepadControl::Button(Button::South),
             rotate_right: GamepadControl::Button(Button::East),
+            rotate_180: Some(GamepadControl::Button(Button::North)),
             hard_drop: GamepadControl::Button(Button::DPadUp),
             soft_drop: GamepadControl::Button(Button::DPadDown),
             hold: GamepadControl::Button(Button::LeftTrigger),
@@ -146,6 +148,14 @@ impl InputSource for UserInput {
                 self.keyboard.rotate_right,
                 self.gamepad.rotate_right,
             ),
+            rotate_180: self
+                .keyboard
+                .rotate_180
+                .map_or(false, |key| keys.contains(&key))
+                || self
+                    .gamepad
+                    .rotate_180
+                    .map_or(false, |control| gamepad_pressed(gamepad, control)),
             hard_drop: self.read_input(
                 keys,
                 gamepad,
@@ -175,15 +185,18 @@ impl UserInput {
         keyboard: VirtualKeyCode,
         gamepad: GamepadControl,
     ) -> bool {
-        keys.contains(&keyboard)
-            || controller.map_or(false, |c| match gamepad {
-                GamepadControl::Button(button) => c.is_pressed(button),
-                GamepadControl::PositiveAxis(axis) => c.value(axis) > 0.5,
-                GamepadControl::NegativeAxis(axis) => c.value(axis) < -0.5,
-            })
+        keys.contains(&keyboard) || gamepad_pressed(controller, gamepad)
     }
 }
 
+fn gamepad_pressed(controller: Option<Gamepad>, gamepad: GamepadControl) -> bool {
+    controller.map_or(false, |c| match gamepad {
+        GamepadControl::Button(button) => c.is_pressed(button),
+        GamepadControl::PositiveAxis(axis) => c.value(axis) > 0.5,
+        GamepadControl::NegativeAxis(axis) => c.value(axis) < -0.5,
+    })
+}
+
 #[derive(Copy, Clone, Debug, Serialize, Deserialize)]
 enum GamepadControl {
     Button(Button),
@@ -234,7 +234,6 @@ impl Default for Options {
     fn default() -> Self {
         let mut p2 = PlayerConfig::default();
         p2.is_bot = true;
-        p2.is_bot_new = 2;
         Options {
             p1: PlayerConfig::default(),
             p2,
@@ -250,8 +249,6 @@ pub struct PlayerConfig<E: Default> {
     bot_config: BotConfig<E>,
     is_bot: bool,
     show_plan: bool,
-    //newfield
-    is_bot_new: u32,
 }
 
 impl<E: Default> Default for PlayerConfig<E> {
@@ -262,8 +259,6 @@ impl<E: Default> Default for PlayerConfig<E> {
             bot_config: Default::default(),
             is_bot: false,
             show_plan: true,
-            //newfield
-            is_bot_new: 1,
         }
     }
 }
@@ -287,12 +282,14 @@ where
                     100.0 / (self.bot_config.speed_limit + 1) as f32
                 ));
             }
+            let mut options = self.bot_config.options;
+            options.movement_costs = self.game.movement_costs();
             #[cfg(not(target_arch = "wasm32"))]
             let result = (
                 Box::new(BotInput::new(
                     cold_clear::Interface::launch(
                         board,
-                        self.bot_config.options,
+                        options,
                         self.bot_config.weights.clone(),
                         self.bot_config.book_path.as_ref().and_then(|path| {
                             let mut book_cache = self.bot_config.book_cache.borrow_mut();
@@ -308,7 +305,6 @@ where
                         }),
                     ),
                     self.bot_config.speed_limit,
-                    self.is_bot_new,
                 )) as Box<_>,
                 name,
             );
@@ -319,7 +315,7 @@ where
                     cold_clear::Interface::launch(
                         "./worker.js",
                         board,
-                        self.bot_config.options,
+                        options,
                         self.bot_config.weights.clone(),
                     )
                     .await,
@@ -17,7 +17,6 @@ pub struct PlayerDrawState {
     show_plan: bool,
     hold_piece: Option<Piece>,
     next_queue: VecDeque<Piece>,
-    game_time: u32,
     combo_splash: Option<(u32, u32)>,
     back_to_back_splash: Option<u32>,
     clear_splash: Option<(&'static str, u32)>,
@@ -41,7 +40,6 @@ impl PlayerDrawState {
             dead: false,
             hold_piece: None,
             next_queue: queue.into_iter().collect(),
-            game_time: 0,
             combo_splash: None,
             back_to_back_splash: None,
             clear_splash: None,
@@ -59,7 +57,6 @@ impl PlayerDrawState {
     ) {
         self.garbage_queue = update.garbage_queue;
         self.info = info_update.or(self.info.take());
-        self.game_time = time;
         if let State::LineClearAnimation(_, ref mut frames) = self.state {
             *frames += 1;
         }
@@ -87,7 +84,7 @@ impl PlayerDrawState {
         for event in &update.events {
             match event {
                 Event::PiecePlaced { piece, locked, .. } => {
-                    self.statistics.update(&locked);
+                    self.statistics.update_at(&locked, time as u64);
                     for &(x, y) in &piece.cells() {
                         self.board[y as usize].set(x as usize, piece.kind.0.color());
                     }
@@ -111,6 +108,7 @@ impl PlayerDrawState {
                     }
                 }
                 Event::PieceHeld(piece) => {
+                    self.statistics.hold_used();
                     self.hold_piece = Some(*piece);
                     self.state = State::Delay;
                 }
@@ -123,17 +121,18 @@ impl PlayerDrawState {
                 }
                 Event::EndOfLineClearDelay => {
                     self.state = State::Delay;
-                    self.board.retain(|row| !row.is_full());
+                    self.board.retain(|row| !row.is_full(10));
                     while !self.board.is_full() {
                         self.board.push(*ColoredRow::EMPTY);
                     }
                 }
-                Event::GarbageAdded(columns) => {
-                    self.board.truncate(40 - columns.len());
-                    for &col in columns {
+                Event::GarbageAdded(garbage) => {
+                    self.statistics.garbage_added(garbage.len() as u64);
+                    self.board.truncate(40 - garbage.len());
+                    for holes in garbage {
                         let mut row = *ColoredRow::EMPTY;
                         for x in 0..10 {
-                            if x != col {
+                            if !holes.contains(&x) {
                                 row.set(x, CellColor::Garbage);
                             }
                         }
@@ -252,27 +251,16 @@ impl PlayerDrawState {
             0.6,
             0,
         );
-        let seconds = self.game_time as f32 / 60.0;
         let mut lines = vec![
             ("Pieces", format!("{}", self.statistics.pieces)),
-            (
-                "PPS",
-                format!("{:.1}", self.statistics.pieces as f32 / seconds),
-            ),
+            ("PPS", format!("{:.1}", self.statistics.pps())),
             ("Lines", format!("{}", self.statistics.lines)),
             ("Attack", format!("{}", self.statistics.attack)),
-            (
-                "APM",
-                format!("{:.1}", self.statistics.attack as f32 / seconds * 60.0),
-            ),
-            (
-                "APP",
-                format!(
-                    "{:.3}",
-                    self.statistics.attack as f32 / self.statistics.pieces as f32
-                ),
-            ),
+            ("APM", format!("{:.1}", self.statistics.apm())),
+            ("APP", format!("{:.3}", self.statistics.app())),
+            ("VS", format!("{:.1}", self.statistics.vs_score())),
             ("Max Ren", format!("{}", self.statistics.max_combo)),
+            ("Max B2B", format!("{}", self.statistics.max_b2b_chain)),
             ("Single", format!("{}", self.statistics.singles)),
             ("Double", format!("{}", self.statistics.doubles)),
             ("Triple", format!("{}", self.statistics.triples)),
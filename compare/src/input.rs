@@ -2,6 +2,7 @@ use std::time::{Duration, Instant};
 
 use battle::{Event, PieceMoveExecutor};
 use cold_clear::evaluation::Evaluator;
+use cold_clear::Options;
 use libtetris::{Board, ColoredRow, Controller, FallingPiece};
 
 pub struct BotInput<E: Evaluator> {
@@ -15,12 +16,12 @@ pub struct BotInput<E: Evaluator> {
 const THINK_AMOUNT: Duration = Duration::from_millis(4);
 
 impl<E: Evaluator> BotInput<E> {
-    pub fn new(board: Board, eval: E) -> Self {
+    pub fn new(board: Board, eval: E, options: Options) -> Self {
         let mut this = BotInput {
             controller: Controller::default(),
             executing: None,
             time_budget: Duration::new(0, 0),
-            bot: cold_clear::BotState::new(board, Default::default(),is_bot_new),
+            bot: cold_clear::BotState::new(board, options),
             eval,
         };
         for _ in 0..180 {
@@ -75,7 +76,7 @@ impl<E: Evaluator> BotInput<E> {
                 }
                 Event::GarbageAdded(_) => {
                     self.bot
-                        .reset(board.get_field(), board.b2b_bonus, board.combo);
+                        .reset(board.get_field(), board.b2b_bonus(), board.combo);
                 }
                 _ => {}
             }
@@ -85,7 +86,7 @@ impl<E: Evaluator> BotInput<E> {
             if let Some(loc) = executor.update(&mut self.controller, board, events) {
                 if loc != expected {
                     self.bot
-                        .reset(board.get_field(), board.b2b_bonus, board.combo);
+                        .reset(board.get_field(), board.b2b_bonus(), board.combo);
                 }
                 self.executing = None;
             }
@@ -2,6 +2,7 @@ use std::collections::VecDeque;
 
 use battle::{Battle, GameConfig, Replay};
 use cold_clear::evaluation::Evaluator;
+use cold_clear::Options;
 use libflate::deflate;
 use rand::prelude::*;
 use serde::{Deserialize, Serialize};
@@ -17,6 +18,10 @@ fn main() {
 
     let p2_eval = changed::Standard::default();
 
+    let p1_options = Options::default();
+
+    let p2_options = Options::default();
+
     let (send, recv) = std::sync::mpsc::channel();
 
     for _ in 0..12 {
@@ -25,7 +30,10 @@ fn main() {
         let send = send.clone();
         std::thread::spawn(move || loop {
             if send
-                .send(do_battle(p1_eval.clone(), p2_eval.clone()))
+                .send(do_battle(
+                    (p1_eval.clone(), p1_options),
+                    (p2_eval.clone(), p2_options),
+                ))
                 .is_err()
             {
                 break;
@@ -63,7 +71,10 @@ fn main() {
     println!("p = {:.4}", p);
 }
 
-fn do_battle(p1: impl Evaluator + Clone, p2: impl Evaluator + Clone) -> (InfoReplay, bool) {
+fn do_battle(
+    (p1, p1_options): (impl Evaluator + Clone, Options),
+    (p2, p2_options): (impl Evaluator + Clone, Options),
+) -> (InfoReplay, bool) {
     let mut battle = Battle::new(
         GameConfig::default(),
         GameConfig::default(),
@@ -72,11 +83,11 @@ fn do_battle(p1: impl Evaluator + Clone, p2: impl Evaluator + Clone) -> (InfoRep
         thread_rng().gen(),
     );
 
-    battle.replay.p1_name = format!("Cold Clear\n{}", p1.name());
-    battle.replay.p2_name = format!("Cold Clear\n{}", p2.name());
+    battle.replay.p1_name = format!("Cold Clear\n{}\n{:?}", p1.name(), p1_options.selection);
+    battle.replay.p2_name = format!("Cold Clear\n{}\n{:?}", p2.name(), p2_options.selection);
 
-    let mut p1 = BotInput::new(battle.player_1.board.to_compressed(), p1);
-    let mut p2 = BotInput::new(battle.player_2.board.to_compressed(), p2);
+    let mut p1 = BotInput::new(battle.player_1.board.to_compressed(), p1, p1_options);
+    let mut p2 = BotInput::new(battle.player_2.board.to_compressed(), p2, p2_options);
 
     let mut p1_info_updates = VecDeque::new();
     let mut p2_info_updates = VecDeque::new();
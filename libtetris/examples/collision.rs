@@ -0,0 +1,131 @@
+//! Times the row bitmask collision check in `Board::obstructed` against checking each cell, and
+//! `find_moves`, on random boards. Times are the best of several runs.
+//!
+//! Run with `cargo run --release -p libtetris --example collision`.
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use libtetris::*;
+use rand::prelude::*;
+use rand::rngs::StdRng;
+
+const PIECES: [Piece; 7] = [
+    Piece::I,
+    Piece::O,
+    Piece::T,
+    Piece::L,
+    Piece::J,
+    Piece::S,
+    Piece::Z,
+];
+const ROTATIONS: [RotationState; 4] = [
+    RotationState::North,
+    RotationState::East,
+    RotationState::South,
+    RotationState::West,
+];
+
+fn random_board(rng: &mut impl Rng, width: usize, height: usize) -> Board {
+    let mut board = Board::with_size(width, height);
+    let stack = rng.gen_range(0, height.min(16));
+    for y in 0..stack {
+        let density = rng.gen_range(0.3, 1.0);
+        for x in 0..width {
+            if rng.gen_bool(density) {
+                board.set_cell_color(x as i32, y as i32, CellColor::Garbage);
+            }
+        }
+    }
+    board
+}
+
+fn obstructed_by_cells(board: &Board, piece: &FallingPiece) -> bool {
+    piece.cells().iter().any(|&(x, y)| board.occupied(x, y))
+}
+
+fn all_positions(width: usize, height: usize) -> impl Iterator<Item = FallingPiece> {
+    PIECES.iter().flat_map(move |&p| {
+        ROTATIONS.iter().flat_map(move |&r| {
+            (-3..width as i32 + 3).flat_map(move |x| {
+                (-3..height as i32 + 3).map(move |y| FallingPiece {
+                    kind: PieceState(p, r),
+                    x,
+                    y,
+                    tspin: TspinStatus::None,
+                })
+            })
+        })
+    })
+}
+
+fn best_of(runs: usize, mut f: impl FnMut()) -> Duration {
+    (0..runs)
+        .map(|_| {
+            let start = Instant::now();
+            f();
+            start.elapsed()
+        })
+        .min()
+        .unwrap()
+}
+
+fn main() {
+    let mut rng = StdRng::seed_from_u64(0);
+
+    let boards: Vec<_> = (0..1000).map(|_| random_board(&mut rng, 10, 40)).collect();
+    let positions: Vec<_> = all_positions(10, 24).collect();
+    let masks = best_of(5, || {
+        for board in &boards[..100] {
+            for piece in &positions {
+                black_box(board.obstructed(black_box(piece)));
+            }
+        }
+    });
+    let cells = best_of(5, || {
+        for board in &boards[..100] {
+            for piece in &positions {
+                black_box(obstructed_by_cells(board, black_box(piece)));
+            }
+        }
+    });
+    let checks = 100 * positions.len() as u32;
+    println!(
+        "{} collision checks: {:?} with row masks ({:?} each), {:?} by cells ({:?} each)",
+        checks,
+        masks,
+        masks / checks,
+        cells,
+        cells / checks
+    );
+
+    for &mode in &[MovementMode::ZeroG, MovementMode::ZeroGComplete] {
+        let mut searches = 0;
+        let elapsed = best_of(5, || {
+            searches = 0;
+            for board in &boards {
+                for &p in &PIECES {
+                    if let Some(spawned) = SpawnRule::Row19Or20.spawn(p, board, RotationSystem::Srs)
+                    {
+                        black_box(find_moves(
+                            board,
+                            spawned,
+                            mode,
+                            RotationSystem::Srs,
+                            None,
+                            MovementCosts::default(),
+                        ));
+                        searches += 1;
+                    }
+                }
+            }
+        });
+        println!(
+            "{:?}: {} searches in {:?} ({:?} per search)",
+            mode,
+            searches,
+            elapsed,
+            elapsed / searches
+        );
+    }
+}
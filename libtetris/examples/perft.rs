@@ -0,0 +1,57 @@
+//! Times perft counts from the empty board for every movement mode, checking `find_moves` against
+//! the exhaustive search in `Perft` along the way. The fixture and random board checks are in the
+//! libtetris tests.
+//!
+//! Run with `cargo run --release -p libtetris --example perft`.
+
+use std::time::Instant;
+
+use libtetris::*;
+
+const MODES: [MovementMode; 5] = [
+    MovementMode::ZeroG,
+    MovementMode::ZeroGComplete,
+    MovementMode::TwentyG,
+    MovementMode::HardDropOnly,
+    MovementMode::HardDropAndTuck,
+];
+const QUEUE: [Piece; 7] = [
+    Piece::T,
+    Piece::I,
+    Piece::O,
+    Piece::L,
+    Piece::J,
+    Piece::S,
+    Piece::Z,
+];
+
+fn main() {
+    for &mode in &MODES {
+        for &flip_kicks in &[None, Some(FlipKicks::Tetrio)] {
+            let perft = Perft {
+                mode,
+                flip_kicks,
+                ..Perft::default()
+            };
+            for depth in 1..=3 {
+                let start = Instant::now();
+                let count = match perft.check(&mut Board::new(), &QUEUE[..depth]) {
+                    Ok(count) => count,
+                    Err(mismatch) => panic!("{:?} {:?}: {}", mode, flip_kicks, mismatch),
+                };
+                let checked = start.elapsed();
+                let start = Instant::now();
+                assert_eq!(count, perft.count(&mut Board::new(), &QUEUE[..depth]));
+                println!(
+                    "{:?} {:?} depth {}: {} (checked in {:?}, counted in {:?})",
+                    mode,
+                    flip_kicks,
+                    depth,
+                    count,
+                    checked,
+                    start.elapsed()
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,247 @@
+use std::convert::TryFrom;
+
+use serde::{Deserialize, Serialize};
+
+use crate::PlacementKind;
+
+/// Describes how many garbage lines line clears send.
+#[derive(Copy, Clone, Debug, Hash, Eq, PartialEq, Serialize, Deserialize)]
+pub struct AttackTable {
+    pub clear1: u32,
+    pub clear2: u32,
+    pub clear3: u32,
+    pub clear4: u32,
+    pub mini_tspin1: u32,
+    pub mini_tspin2: u32,
+    pub mini_tspin3: u32,
+    pub tspin1: u32,
+    pub tspin2: u32,
+    pub tspin3: u32,
+    pub mini_spin1: u32,
+    pub mini_spin2: u32,
+    pub mini_spin3: u32,
+    pub spin1: u32,
+    pub spin2: u32,
+    pub spin3: u32,
+    pub back_to_back: BackToBackAttack,
+    pub combo: ComboAttack,
+    pub perfect_clear: PerfectClearAttack,
+}
+
+#[derive(Copy, Clone, Debug, Hash, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "ComboAttackData")]
+pub enum ComboAttack {
+    /// Extra lines sent, indexed by combo. Combos past the end of the table use the last entry.
+    /// `len` is between 1 and 16; deserializing rejects other lengths.
+    Table { lines: [u32; 16], len: usize },
+    /// TETR.IO style: attack is multiplied by `1 + combo / 4`, and clears that would otherwise
+    /// send nothing send `ln(1 + 1.25 * combo)` lines.
+    Multiplier,
+}
+
+#[derive(Copy, Clone, Debug, Hash, Eq, PartialEq, Serialize, Deserialize)]
+pub enum BackToBackAttack {
+    /// Every back-to-back clear sends this many extra lines.
+    Flat(u32),
+    /// TETR.IO season 1 style: the bonus grows with the length of the chain, sending
+    /// `1 + ln(1 + 0.8 * b2b)` extra lines where `b2b` is the number of back-to-backs so far.
+    Levels,
+    /// TETR.IO season 2 style: every back-to-back clear sends `bonus` extra lines, and breaking a
+    /// chain of at least `threshold` back-to-backs sends one line for each back-to-back.
+    Surge { bonus: u32, threshold: u32 },
+}
+
+#[derive(Copy, Clone, Debug, Hash, Eq, PartialEq, Serialize, Deserialize)]
+pub enum PerfectClearAttack {
+    /// The perfect clear sends this many lines instead of the normal attack.
+    Replace(u32),
+    /// The perfect clear sends this many lines in addition to the normal attack.
+    Add(u32),
+}
+
+impl AttackTable {
+    /// Puyo Puyo Tetris versus.
+    pub fn ppt() -> Self {
+        AttackTable {
+            clear1: 0,
+            clear2: 1,
+            clear3: 2,
+            clear4: 4,
+            mini_tspin1: 0,
+            mini_tspin2: 1,
+            mini_tspin3: 2,
+            tspin1: 2,
+            tspin2: 4,
+            tspin3: 6,
+            mini_spin1: 0,
+            mini_spin2: 1,
+            mini_spin3: 2,
+            spin1: 2,
+            spin2: 4,
+            spin3: 6,
+            back_to_back: BackToBackAttack::Flat(1),
+            combo: ComboAttack::table(&[0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 4, 5]),
+            perfect_clear: PerfectClearAttack::Replace(10),
+        }
+    }
+
+    /// TETR.IO's multiplier based combos and back-to-back levels.
+    pub fn tetrio() -> Self {
+        AttackTable {
+            back_to_back: BackToBackAttack::Levels,
+            combo: ComboAttack::Multiplier,
+            perfect_clear: PerfectClearAttack::Add(10),
+            ..AttackTable::ppt()
+        }
+    }
+
+    pub fn jstris() -> Self {
+        AttackTable {
+            combo: ComboAttack::table(&[0, 0, 1, 1, 1, 2, 2, 3, 3, 4, 4, 4, 5]),
+            perfect_clear: PerfectClearAttack::Add(10),
+            ..AttackTable::ppt()
+        }
+    }
+
+    pub fn tetris99() -> Self {
+        AttackTable {
+            combo: ComboAttack::table(&[0, 1, 1, 2, 2, 3, 3, 4]),
+            perfect_clear: PerfectClearAttack::Replace(10),
+            ..AttackTable::ppt()
+        }
+    }
+
+    /// The number of lines this kind of placement sends on its own.
+    pub fn base_attack(&self, kind: PlacementKind) -> u32 {
+        use PlacementKind::*;
+        match kind {
+            None | MiniTspin | Tspin | MiniSpin | Spin => 0,
+            Clear1 => self.clear1,
+            Clear2 => self.clear2,
+            Clear3 => self.clear3,
+            Clear4 => self.clear4,
+            MiniTspin1 => self.mini_tspin1,
+            MiniTspin2 => self.mini_tspin2,
+            MiniTspin3 => self.mini_tspin3,
+            Tspin1 => self.tspin1,
+            Tspin2 => self.tspin2,
+            Tspin3 => self.tspin3,
+            MiniSpin1 => self.mini_spin1,
+            MiniSpin2 => self.mini_spin2,
+            MiniSpin3 => self.mini_spin3,
+            Spin1 => self.spin1,
+            Spin2 => self.spin2,
+            Spin3 => self.spin3,
+        }
+    }
+
+    /// The number of lines a line clear sends, not counting perfect clear bonuses.
+    ///
+    /// `b2b` is the number of back-to-backs in the chain including this clear, or 0 if it isn't a
+    /// back-to-back clear. `combo` is the number of consecutive line clears before this one.
+    pub fn clear_attack(&self, kind: PlacementKind, b2b: u32, combo: u32) -> u32 {
+        let base = self.base_attack(kind) + self.back_to_back_attack(b2b);
+        base + self.combo_attack(base, combo)
+    }
+
+    /// The extra lines sent by a back-to-back clear. `b2b` is the number of back-to-backs in the
+    /// chain including this clear.
+    pub fn back_to_back_attack(&self, b2b: u32) -> u32 {
+        if b2b == 0 {
+            return 0;
+        }
+        match self.back_to_back {
+            BackToBackAttack::Flat(lines) => lines,
+            BackToBackAttack::Levels => (1.0 + (1.0 + 0.8 * b2b as f64).ln()) as u32,
+            BackToBackAttack::Surge { bonus, .. } => bonus,
+        }
+    }
+
+    /// The lines released when a chain of `b2b` back-to-backs is broken.
+    pub fn surge_attack(&self, b2b: u32) -> u32 {
+        match self.back_to_back {
+            BackToBackAttack::Surge { threshold, .. } if b2b > 0 && b2b >= threshold => b2b,
+            _ => 0,
+        }
+    }
+
+    /// The extra lines sent by a combo, given the attack the clear would otherwise send.
+    pub fn combo_attack(&self, base: u32, combo: u32) -> u32 {
+        match self.combo {
+            ComboAttack::Table { lines, len } => lines[(combo as usize).min(len - 1)],
+            ComboAttack::Multiplier => {
+                if base == 0 {
+                    (1.0 + 1.25 * combo as f64).ln() as u32
+                } else {
+                    (base as f64 * (1.0 + 0.25 * combo as f64)) as u32 - base
+                }
+            }
+        }
+    }
+
+    /// The number of lines sent by a perfect clear, given the normal attack of the clear.
+    pub fn perfect_clear_attack(&self, attack: u32) -> u32 {
+        match self.perfect_clear {
+            PerfectClearAttack::Replace(lines) => lines,
+            PerfectClearAttack::Add(lines) => attack + lines,
+        }
+    }
+}
+
+impl Default for AttackTable {
+    fn default() -> Self {
+        AttackTable::ppt()
+    }
+}
+
+/// `ComboAttack` as it is serialized, before the length of the table is checked.
+#[derive(Deserialize)]
+enum ComboAttackData {
+    Table { lines: [u32; 16], len: usize },
+    Multiplier,
+}
+
+impl TryFrom<ComboAttackData> for ComboAttack {
+    type Error = &'static str;
+
+    fn try_from(v: ComboAttackData) -> Result<Self, Self::Error> {
+        match v {
+            ComboAttackData::Table { len, .. } if !(1..=16).contains(&len) => {
+                Err("combo table length must be between 1 and 16")
+            }
+            ComboAttackData::Table { lines, len } => Ok(ComboAttack::Table { lines, len }),
+            ComboAttackData::Multiplier => Ok(ComboAttack::Multiplier),
+        }
+    }
+}
+
+impl ComboAttack {
+    /// Builds a combo table. Panics if `lines` is empty or longer than 16 entries.
+    pub fn table(lines: &[u32]) -> Self {
+        assert!(!lines.is_empty() && lines.len() <= 16);
+        let mut table = [0; 16];
+        table[..lines.len()].copy_from_slice(lines);
+        ComboAttack::Table {
+            lines: table,
+            len: lines.len(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn combo_table_length_is_checked() {
+        let table = |len| {
+            ComboAttack::try_from(ComboAttackData::Table {
+                lines: [1; 16],
+                len,
+            })
+        };
+        assert!(table(0).is_err());
+        assert!(table(17).is_err());
+        assert_eq!(table(16), Ok(ComboAttack::table(&[1; 16])));
+    }
+}
@@ -7,39 +7,98 @@ use serde::{Deserialize, Serialize};
 
 use crate::*;
 
+/// The widest board supported.
+pub const MAX_WIDTH: usize = 16;
+/// The tallest board supported.
+pub const MAX_HEIGHT: usize = 40;
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Board<R = u16> {
-    cells: ArrayVec<[R; 40]>,
-    column_heights: [i32; 10],
+    cells: ArrayVec<[R; MAX_HEIGHT]>,
+    column_heights: [i32; MAX_WIDTH],
+    width: i32,
+    height: i32,
+    /// Zobrist hash of the filled cells.
+    cells_hash: u64,
     pub combo: u32,
-    pub b2b_bonus: bool,
+    /// The number of consecutive hard line clears. The next hard clear is a back-to-back if this
+    /// is nonzero.
+    pub b2b_chain: u32,
     pub hold_piece: Option<Piece>,
     next_pieces: VecDeque<Piece>,
-    pub bag: EnumSet<Piece>,
+    /// The number of pieces ever taken from the queue, so `undo` can tell how many to put back.
+    #[serde(skip)]
+    pieces_taken: u32,
+    pub randomizer: PieceRandomizer,
+    pub spin_rule: SpinRule,
+    pub attack: AttackTable,
+    pub top_out: TopOutRules,
+}
+
+/// The state `Board::undo` needs to take back a placement made with `Board::lock_piece_undoable`.
+#[derive(Clone, Debug)]
+pub struct LockUndo<R = u16> {
+    piece: FallingPiece,
+    cleared: ArrayVec<[(i32, R); 4]>,
+    column_heights: [i32; MAX_WIDTH],
+    cells_hash: u64,
+    combo: u32,
+    b2b_chain: u32,
+    hold_piece: Option<Piece>,
+    queue_len: usize,
+    queue_front: ArrayVec<[Piece; 2]>,
+    pieces_taken: u32,
+    randomizer: PieceRandomizer,
 }
 
 pub trait Row: Copy + Clone + 'static {
     fn set(&mut self, x: usize, color: CellColor);
     fn get(&self, x: usize) -> bool;
-    fn is_full(&self) -> bool;
+    /// Whether the first `width` cells of the row are filled.
+    fn is_full(&self, width: usize) -> bool;
     fn is_empty(&self) -> bool;
     fn cell_color(&self, x: usize) -> CellColor;
 
+    /// Whether any of the cells set in `mask` are filled.
+    fn collides(&self, mask: u16) -> bool {
+        (0..MAX_WIDTH).any(|x| mask & 1 << x != 0 && self.get(x))
+    }
+
     const EMPTY: &'static Self;
     const SOLID: &'static Self;
 }
 
 impl<R: Row> Board<R> {
-    /// Creates a blank board with an empty queue.
+    /// Creates a blank 10x40 board with an empty queue.
     pub fn new() -> Self {
+        Board::with_size(10, 40)
+    }
+
+    /// Creates a blank board of the given size with an empty queue. Cells outside the board are
+    /// solid, so the height is a hard ceiling.
+    ///
+    /// The bot's `Standard` evaluator only understands 10 wide boards, and panics on narrower
+    /// ones. Other sizes need an evaluator written for them.
+    ///
+    /// Panics if the board is wider than `MAX_WIDTH`, taller than `MAX_HEIGHT`, or smaller than 4
+    /// cells in either dimension.
+    pub fn with_size(width: usize, height: usize) -> Self {
+        assert!((4..=MAX_WIDTH).contains(&width) && (4..=MAX_HEIGHT).contains(&height));
         Board {
-            cells: [*R::EMPTY; 40].into(),
-            column_heights: [0; 10],
+            cells: [*R::EMPTY; MAX_HEIGHT].into(),
+            column_heights: [0; MAX_WIDTH],
+            width: width as i32,
+            height: height as i32,
+            cells_hash: 0,
             combo: 0,
-            b2b_bonus: false,
+            b2b_chain: 0,
             hold_piece: None,
             next_pieces: VecDeque::new(),
-            bag: EnumSet::all(),
+            pieces_taken: 0,
+            randomizer: PieceRandomizer::default(),
+            spin_rule: SpinRule::default(),
+            attack: AttackTable::default(),
+            top_out: TopOutRules::default(),
         }
     }
 
@@ -52,37 +111,42 @@ impl<R: Row> Board<R> {
         combo: u32,
     ) -> Self {
         let mut board = Board {
-            cells: [*R::EMPTY; 40].into(),
-            column_heights: [0; 10],
+            cells: [*R::EMPTY; MAX_HEIGHT].into(),
+            column_heights: [0; MAX_WIDTH],
+            width: 10,
+            height: 40,
+            cells_hash: 0,
             combo: combo,
-            b2b_bonus: b2b,
+            b2b_chain: b2b as u32,
             hold_piece: hold,
             next_pieces: VecDeque::new(),
-            bag: if bag_remain.is_empty() {
-                EnumSet::all()
-            } else {
-                bag_remain
-            },
+            pieces_taken: 0,
+            randomizer: PieceRandomizer::default(),
+            spin_rule: SpinRule::default(),
+            attack: AttackTable::default(),
+            top_out: TopOutRules::default(),
         };
+        board.randomizer.set_bag(bag_remain);
         board.set_field(field);
         board
     }
 
-    /// Randomly selects a piece from the bag.
+    /// Randomly selects the next piece using the board's randomizer.
     ///
-    /// This function does not remove the generated piece from the bag.
+    /// This function does not update the randomizer state.
     /// Use add_next_piece() to add it to the queue.
     pub fn generate_next_piece(&self, rng: &mut impl rand::Rng) -> Piece {
-        use rand::prelude::*;
-        let choices: ArrayVec<[_; 7]> = self.bag.iter().collect();
-        *choices.choose(rng).unwrap()
+        self.randomizer.generate(rng)
     }
 
     /// Retrieves the next piece in the queue.
     ///
     /// If the queue is empty, returns the set of possible next pieces.
     pub fn get_next_piece(&self) -> Result<Piece, EnumSet<Piece>> {
-        self.next_pieces.front().copied().ok_or(self.bag)
+        self.next_pieces
+            .front()
+            .copied()
+            .ok_or_else(|| self.randomizer.possible())
     }
 
     /// Retrieves the piece after the next piece in the queue if it is known.
@@ -90,24 +154,27 @@ impl<R: Row> Board<R> {
         self.next_pieces.get(1).copied()
     }
 
-    /// Adds the piece to the next queue and removes it from the bag.
-    ///
-    /// If the bag becomes empty, the bag is refilled.
+    /// Adds the piece to the next queue and deals it from the randomizer.
     pub fn add_next_piece(&mut self, piece: Piece) {
-        self.bag.remove(piece);
-        if self.bag.is_empty() {
-            self.bag = EnumSet::all();
-        }
+        self.randomizer.deal(piece);
         self.next_pieces.push_back(piece);
     }
 
-    fn remove_cleared_lines(&mut self) -> ArrayVec<[i32; 4]> {
+    /// Removes the full rows, returning their indices and contents.
+    fn remove_cleared_lines(&mut self) -> ArrayVec<[(i32, R); 4]> {
         let mut cleared = ArrayVec::new();
         let mut lineno = 0;
+        let width = self.width as usize;
+        let top = self.stack_height();
+        // Only rows from the lowest cleared line up move, so only they need to be rehashed.
+        let lowest = (0..top).find(|&y| self.cells[y].is_full(width));
+        if let Some(lowest) = lowest {
+            self.cells_hash ^= self.rows_hash(lowest..top);
+        }
         self.cells.retain(|r| {
-            let full = r.is_full();
+            let full = r.is_full(width);
             if full {
-                cleared.push(lineno);
+                cleared.push((lineno, *r));
             }
             lineno += 1;
             !full
@@ -116,7 +183,7 @@ impl<R: Row> Board<R> {
         for _ in 0..cleared.len() {
             self.cells.push(*R::EMPTY);
         }
-        for x in 0..10 {
+        for x in 0..width {
             self.column_heights[x] -= cleared.len() as i32;
             while self.column_heights[x] > 0
                 && !self.cells[self.column_heights[x] as usize - 1].get(x)
@@ -124,24 +191,79 @@ impl<R: Row> Board<R> {
                 self.column_heights[x] -= 1;
             }
         }
+        if let Some(lowest) = lowest {
+            self.cells_hash ^= self.rows_hash(lowest..top);
+        }
         cleared
     }
 
+    fn stack_height(&self) -> usize {
+        self.column_heights().iter().copied().max().unwrap() as usize
+    }
+
+    fn rows_hash(&self, rows: std::ops::Range<usize>) -> u64 {
+        let mut hash = 0;
+        for y in rows {
+            for x in 0..self.width as usize {
+                if self.cells[y].get(x) {
+                    hash ^= zobrist::cell(x, y);
+                }
+            }
+        }
+        hash
+    }
+
+    /// A 64-bit Zobrist hash of the position: the field, hold piece, reserve piece, bag, combo and
+    /// back-to-back chain. The reserve piece is the hold piece, or the next piece if hold is
+    /// empty, and the bag is `next_bag()`.
+    ///
+    /// The field part of the hash is maintained incrementally. The rest is cheap enough to mix in
+    /// here, which keeps the hash correct when the public fields are assigned directly.
+    pub fn zobrist_hash(&self) -> u64 {
+        let mut hash = self.cells_hash
+            ^ zobrist::hold(self.hold_piece)
+            ^ zobrist::reserve(
+                self.hold_piece
+                    .or_else(|| self.next_pieces.front().copied()),
+            )
+            ^ zobrist::combo(self.combo)
+            ^ zobrist::b2b(self.b2b_chain);
+        for p in self.next_bag() {
+            hash ^= zobrist::bag(p);
+        }
+        hash
+    }
+
+    pub fn width(&self) -> i32 {
+        self.width
+    }
+
+    pub fn height(&self) -> i32 {
+        self.height
+    }
+
     pub fn occupied(&self, x: i32, y: i32) -> bool {
-        x < 0 || y < 0 || x >= 10 || y >= 40 || (self.cells[y as usize].get(x as usize))
+        x < 0
+            || y < 0
+            || x >= self.width
+            || y >= self.height
+            || self.cells[y as usize].get(x as usize)
     }
 
     pub fn get_row(&self, y: i32) -> &R {
         if y < 0 {
             R::SOLID
-        } else if y >= 40 {
-            R::EMPTY
+        } else if y >= self.height {
+            R::SOLID
         } else {
             &self.cells[y as usize]
         }
     }
 
     pub fn set_cell_color(&mut self, x: i32, y: i32, color: CellColor) {
+        if self.cells[y as usize].get(x as usize) != (color != CellColor::Empty) {
+            self.cells_hash ^= zobrist::cell(x as usize, y as usize);
+        }
         self.cells[y as usize].set(x as usize, color);
         let h = &mut self.column_heights[x as usize];
         if color != CellColor::Empty {
@@ -154,7 +276,23 @@ impl<R: Row> Board<R> {
     }
 
     pub fn obstructed(&self, piece: &FallingPiece) -> bool {
-        piece.cells().iter().any(|&(x, y)| self.occupied(x, y))
+        // Checking whole rows against the piece's row masks is much faster than checking the
+        // cells one at a time, and this is the hottest function in move generation.
+        let mask = piece.kind.mask();
+        let left = piece.x + mask.left;
+        let bottom = piece.y + mask.bottom;
+        if left < 0
+            || piece.x + mask.right >= self.width
+            || bottom < 0
+            || bottom + mask.height as i32 > self.height
+        {
+            return true;
+        }
+        let rows = &self.cells[bottom as usize..bottom as usize + mask.height];
+        mask.rows
+            .iter()
+            .zip(rows)
+            .any(|(&cells, row)| row.collides(cells << left))
     }
 
     pub fn above_stack(&self, piece: &FallingPiece) -> bool {
@@ -170,52 +308,114 @@ impl<R: Row> Board<R> {
 
     /// Does all logic associated with locking a piece.
     ///
-    /// Clears lines, detects clear kind, calculates garbage, maintains combo and back-to-back
-    /// state, detects perfect clears, detects lockout.
+    /// Clears lines, detects clear kind, calculates garbage using the board's attack table,
+    /// maintains combo and back-to-back state, detects perfect clears, detects lockout using the
+    /// board's top out rules.
     pub fn lock_piece(&mut self, piece: FallingPiece) -> LockResult {
-        let mut locked_out = true;
+        self.lock(piece).0
+    }
+
+    /// Locks the piece like `lock_piece`, also returning what is needed to `undo` it.
+    pub fn lock_piece_undoable(&mut self, piece: FallingPiece) -> (LockResult, LockUndo<R>) {
+        let column_heights = self.column_heights;
+        let cells_hash = self.cells_hash;
+        let combo = self.combo;
+        let b2b_chain = self.b2b_chain;
+        let (result, cleared) = self.lock(piece);
+        let undo = LockUndo {
+            piece,
+            cleared,
+            column_heights,
+            cells_hash,
+            combo,
+            b2b_chain,
+            hold_piece: self.hold_piece,
+            queue_len: self.next_pieces.len(),
+            queue_front: self.next_pieces.iter().copied().take(2).collect(),
+            pieces_taken: self.pieces_taken,
+            randomizer: self.randomizer,
+        };
+        (result, undo)
+    }
+
+    /// Restores the board to how it was before the placement `undo` was made for. Undos must be
+    /// applied in the reverse order of their placements.
+    ///
+    /// This also restores the hold piece and the queue. Pieces added to the queue since the
+    /// placement are removed, and pieces taken from the queue are put back. Panics if more than
+    /// two pieces that were in the queue at the placement were taken, which is more than placing a
+    /// piece from hold can take.
+    pub fn undo(&mut self, undo: LockUndo<R>) {
+        for &(y, row) in &undo.cleared {
+            self.cells.pop();
+            self.cells.insert(y as usize, row);
+        }
+        for &(x, y) in &undo.piece.cells() {
+            self.cells[y as usize].set(x as usize, CellColor::Empty);
+        }
+        self.column_heights = undo.column_heights;
+        self.cells_hash = undo.cells_hash;
+        self.combo = undo.combo;
+        self.b2b_chain = undo.b2b_chain;
+        self.hold_piece = undo.hold_piece;
+
+        // pieces taken past the end of the old queue were added after the placement
+        let taken =
+            (self.pieces_taken.wrapping_sub(undo.pieces_taken) as usize).min(undo.queue_len);
+        assert!(
+            taken <= undo.queue_front.len(),
+            "too many pieces taken from the queue to undo"
+        );
+        self.next_pieces.truncate(undo.queue_len - taken);
+        for &p in undo.queue_front[..taken].iter().rev() {
+            self.next_pieces.push_front(p);
+        }
+        self.pieces_taken = undo.pieces_taken;
+        self.randomizer = undo.randomizer;
+    }
+
+    fn lock(&mut self, piece: FallingPiece) -> (LockResult, ArrayVec<[(i32, R); 4]>) {
+        let locked_out = self
+            .top_out
+            .locked_out(piece.cells().iter().map(|&(_, y)| y));
         for &(x, y) in &piece.cells() {
             self.cells[y as usize].set(x as usize, piece.kind.0.color());
+            self.cells_hash ^= zobrist::cell(x as usize, y as usize);
             if self.column_heights[x as usize] < y + 1 {
                 self.column_heights[x as usize] = y + 1;
             }
-            if y < 20 {
-                locked_out = false;
-            }
         }
         let cleared = self.remove_cleared_lines();
 
-        let placement_kind = PlacementKind::get(cleared.len(), piece.tspin);
-
-        let mut garbage_sent = placement_kind.garbage();
+        let placement_kind = PlacementKind::get(cleared.len(), piece.tspin, piece.kind.0);
 
+        let mut garbage_sent = 0;
         let mut did_b2b = false;
+        let mut surge = 0;
         if placement_kind.is_clear() {
+            let mut b2b = 0;
             if placement_kind.is_hard() {
-                if self.b2b_bonus {
-                    garbage_sent += 1;
-                    did_b2b = true;
-                }
-                self.b2b_bonus = true;
+                did_b2b = self.b2b_bonus();
+                b2b = self.b2b_chain;
+                self.b2b_chain += 1;
             } else {
-                self.b2b_bonus = false;
+                surge = self.attack.surge_attack(self.b2b_chain.saturating_sub(1));
+                self.b2b_chain = 0;
             }
 
-            if self.combo as usize >= COMBO_GARBAGE.len() {
-                garbage_sent += COMBO_GARBAGE.last().unwrap();
-            } else {
-                garbage_sent += COMBO_GARBAGE[self.combo as usize];
-            }
+            garbage_sent = self.attack.clear_attack(placement_kind, b2b, self.combo);
 
             self.combo += 1;
         } else {
             self.combo = 0;
         }
 
-        let perfect_clear = self.column_heights == [0; 10];
+        let perfect_clear = self.column_heights.iter().all(|&h| h == 0);
         if perfect_clear {
-            garbage_sent = 10;
+            garbage_sent = self.attack.perfect_clear_attack(garbage_sent);
         }
+        // The surge is released even if a perfect clear replaces the clear's attack.
+        garbage_sent += surge;
 
         let l = LockResult {
             placement_kind,
@@ -228,10 +428,26 @@ impl<R: Row> Board<R> {
                 Some(self.combo - 1)
             },
             b2b: did_b2b,
-            cleared_lines: cleared,
+            b2b_chain: self.b2b_chain,
+            surge,
+            cleared_lines: cleared.iter().map(|&(y, _)| y).collect(),
         };
 
-        l
+        (l, cleared)
+    }
+
+    /// Whether the next hard line clear is a back-to-back.
+    pub fn b2b_bonus(&self) -> bool {
+        self.b2b_chain != 0
+    }
+
+    /// Sets whether back-to-back is active, keeping the current chain if it already is.
+    pub fn set_b2b_bonus(&mut self, b2b: bool) {
+        if !b2b {
+            self.b2b_chain = 0;
+        } else if self.b2b_chain == 0 {
+            self.b2b_chain = 1;
+        }
     }
 
     /// Holds the passed piece, returning the previous hold piece.
@@ -249,17 +465,27 @@ impl<R: Row> Board<R> {
 
     /// Returns the piece that should be spawned, or None if the queue is empty.
     pub fn advance_queue(&mut self) -> Option<Piece> {
-        self.next_pieces.pop_front()
+        let piece = self.next_pieces.pop_front()?;
+        self.pieces_taken = self.pieces_taken.wrapping_add(1);
+        Some(piece)
     }
 
-    pub fn column_heights(&self) -> &[i32; 10] {
-        &self.column_heights
+    pub fn column_heights(&self) -> &[i32] {
+        &self.column_heights[..self.width as usize]
     }
 
+    /// Adds a row of garbage with a hole at `col`, returning whether the player topped out under
+    /// the board's top out rules.
     pub fn add_garbage(&mut self, col: usize) -> bool {
+        self.add_garbage_with_holes(&[col])
+    }
+
+    /// Adds a row of garbage with holes at each of `holes`, returning whether the player topped
+    /// out under the board's top out rules.
+    pub fn add_garbage_with_holes(&mut self, holes: &[usize]) -> bool {
         let mut row = *R::EMPTY;
-        for x in 0..10 {
-            if x == col {
+        for x in 0..self.width as usize {
+            if holes.contains(&x) {
                 if self.column_heights[x] != 0 {
                     self.column_heights[x] += 1;
                 }
@@ -268,9 +494,31 @@ impl<R: Row> Board<R> {
                 self.column_heights[x] += 1;
             }
         }
-        let dead = self.cells.pop().map_or(false, |r| !r.is_empty());
+        let top = self.height as usize - 1;
+        let pushed_out = !self.cells[top].is_empty();
+        self.cells.pop();
         self.cells.insert(0, row);
-        dead
+        if top + 1 < MAX_HEIGHT {
+            // The row pushed past the ceiling is gone.
+            self.cells[top + 1] = *R::EMPTY;
+        }
+        for x in 0..self.width as usize {
+            if self.column_heights[x] > self.height {
+                // The top of the column was pushed out, so it may now be lower than the ceiling.
+                self.column_heights[x] = (0..self.height)
+                    .rev()
+                    .find(|&y| self.cells[y as usize].get(x))
+                    .map_or(0, |y| y + 1);
+            }
+        }
+        // Every row moved up, so the whole field needs to be rehashed.
+        self.cells_hash = self.rows_hash(0..self.stack_height());
+        let topped_out = self.top_out.garbage_top_out
+            && self
+                .column_heights()
+                .iter()
+                .any(|&h| h > self.top_out.visible_rows);
+        (pushed_out && self.top_out.garbage_push_out) || topped_out
     }
 
     pub fn to_compressed(&self) -> Board {
@@ -280,34 +528,43 @@ impl<R: Row> Board<R> {
                 .iter()
                 .map(|r| {
                     let mut row = 0;
-                    for x in 0..10 {
+                    for x in 0..self.width as usize {
                         row.set(x, r.cell_color(x));
                     }
                     row
                 })
                 .collect(),
-            b2b_bonus: self.b2b_bonus,
+            b2b_chain: self.b2b_chain,
             combo: self.combo,
             column_heights: self.column_heights,
+            width: self.width,
+            height: self.height,
+            cells_hash: self.cells_hash,
             next_pieces: self.next_pieces.clone(),
+            pieces_taken: self.pieces_taken,
             hold_piece: self.hold_piece,
-            bag: self.bag,
+            randomizer: self.randomizer,
+            spin_rule: self.spin_rule,
+            attack: self.attack,
+            top_out: self.top_out,
         }
     }
 
+    /// Sets the field of a 10 wide board. Cells that are outside the board are ignored.
     pub fn set_field(&mut self, field: [[bool; 10]; 40]) {
         self.cells.clear();
-        self.column_heights = [0; 10];
-        for y in 0..40 {
+        self.column_heights = [0; MAX_WIDTH];
+        for y in 0..MAX_HEIGHT {
             let mut r = *R::EMPTY;
-            for x in 0..10 {
-                if field[y][x] {
+            for x in 0..10.min(self.width as usize) {
+                if field[y][x] && y < self.height as usize {
                     r.set(x, CellColor::Garbage);
                     self.column_heights[x] = y as i32 + 1;
                 }
             }
             self.cells.push(r)
         }
+        self.cells_hash = self.rows_hash(0..self.stack_height());
     }
 
     pub fn get_field(&self) -> [[bool; 10]; 40] {
@@ -320,15 +577,17 @@ impl<R: Row> Board<R> {
         field
     }
 
+    /// The pieces that could be dealt before the next queue was dealt. For a 7-bag, these are the
+    /// pieces that remained in the bag.
+    ///
+    /// This rewinds the randomizer through the queue with `Randomizer::undeal`, so it is exact
+    /// for 7-bags, 14-bags and pure random, and a superset of the real set for the others.
     pub fn next_bag(&self) -> EnumSet<Piece> {
-        let mut bag = self.bag;
+        let mut randomizer = self.randomizer;
         for p in self.next_queue().rev() {
-            if bag == EnumSet::all() {
-                bag = EnumSet::empty();
-            }
-            bag.insert(p);
+            randomizer.undeal(p);
         }
-        bag
+        randomizer.possible()
     }
 }
 
@@ -346,8 +605,14 @@ impl Row for u16 {
         *self & (1 << x) != 0
     }
 
-    fn is_full(&self) -> bool {
-        self == Self::SOLID
+    #[inline]
+    fn collides(&self, mask: u16) -> bool {
+        *self & mask != 0
+    }
+
+    fn is_full(&self, width: usize) -> bool {
+        let mask = ((1u32 << width) - 1) as u16;
+        *self & mask == mask
     }
 
     fn is_empty(&self) -> bool {
@@ -362,16 +627,16 @@ impl Row for u16 {
         }
     }
 
-    const SOLID: &'static u16 = &0b11111_11111;
+    const SOLID: &'static u16 = &!0;
     const EMPTY: &'static u16 = &0;
 }
 
 #[derive(Copy, Clone, Debug)]
-pub struct ColoredRow([CellColor; 10]);
+pub struct ColoredRow([CellColor; MAX_WIDTH]);
 
 impl Default for ColoredRow {
     fn default() -> Self {
-        ColoredRow([CellColor::Empty; 10])
+        ColoredRow([CellColor::Empty; MAX_WIDTH])
     }
 }
 
@@ -384,8 +649,8 @@ impl Row for ColoredRow {
         self.0[x] != CellColor::Empty
     }
 
-    fn is_full(&self) -> bool {
-        self.0.iter().all(|&c| c != CellColor::Empty)
+    fn is_full(&self, width: usize) -> bool {
+        self.0[..width].iter().all(|&c| c != CellColor::Empty)
     }
 
     fn cell_color(&self, x: usize) -> CellColor {
@@ -396,6 +661,147 @@ impl Row for ColoredRow {
         self.0.iter().all(|&c| c == CellColor::Empty)
     }
 
-    const SOLID: &'static Self = &ColoredRow([CellColor::Unclearable; 10]);
-    const EMPTY: &'static Self = &ColoredRow([CellColor::Empty; 10]);
+    const SOLID: &'static Self = &ColoredRow([CellColor::Unclearable; MAX_WIDTH]);
+    const EMPTY: &'static Self = &ColoredRow([CellColor::Empty; MAX_WIDTH]);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::prelude::*;
+    use rand::rngs::StdRng;
+
+    fn random_board<R: Row>(rng: &mut impl Rng, width: usize, height: usize) -> Board<R> {
+        let mut board = Board::with_size(width, height);
+        let stack = rng.gen_range(0, height.min(16));
+        for y in 0..stack {
+            let density = rng.gen_range(0.3, 1.0);
+            for x in 0..width {
+                if rng.gen_bool(density) {
+                    board.set_cell_color(x as i32, y as i32, CellColor::Garbage);
+                }
+            }
+        }
+        board
+    }
+
+    fn check_obstructed<R: Row>(seed: u64) {
+        let mut rng = StdRng::seed_from_u64(seed);
+        for &(width, height) in &[(10, 40), (10, 22), (4, 8), (16, 40)] {
+            for _ in 0..20 {
+                let board: Board<R> = random_board(&mut rng, width, height);
+                for kind in EnumSet::<Piece>::all() {
+                    let mut kind = PieceState(kind, RotationState::North);
+                    for _ in 0..4 {
+                        kind.1.cw();
+                        for x in -3..width as i32 + 3 {
+                            for y in -3..height as i32 + 3 {
+                                let piece = FallingPiece {
+                                    kind,
+                                    x,
+                                    y,
+                                    tspin: TspinStatus::None,
+                                };
+                                let by_cells =
+                                    piece.cells().iter().any(|&(x, y)| board.occupied(x, y));
+                                assert_eq!(board.obstructed(&piece), by_cells, "{:?}", piece);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn obstructed_matches_cells() {
+        check_obstructed::<u16>(0);
+        check_obstructed::<ColoredRow>(1);
+    }
+
+    #[test]
+    fn garbage_pushes_out_of_short_boards() {
+        let mut board: Board = "width: 4\nheight: 4\n#...\n....\n....\n.###\n"
+            .parse()
+            .unwrap();
+        board.add_garbage(0);
+        let expected = "width: 4\nheight: 4\n.###\n.###\n";
+        assert_eq!(board.to_string(), expected);
+        assert_eq!(board.column_heights(), &[0, 2, 2, 2]);
+        let reparsed: Board = expected.parse().unwrap();
+        assert_eq!(board.zobrist_hash(), reparsed.zobrist_hash());
+
+        for y in 4..MAX_HEIGHT as i32 {
+            assert!(board.occupied(0, y));
+            assert_eq!(board.get_row(y), <u16 as Row>::SOLID);
+        }
+    }
+
+    fn assert_same(board: &Board<ColoredRow>, expected: &Board<ColoredRow>) {
+        assert_eq!(board.to_string(), expected.to_string());
+        assert_eq!(board.column_heights(), expected.column_heights());
+        assert_eq!(board.combo, expected.combo);
+        assert_eq!(board.b2b_chain, expected.b2b_chain);
+        assert_eq!(board.hold_piece, expected.hold_piece);
+        assert!(board.next_queue().eq(expected.next_queue()));
+        assert_eq!(board.randomizer, expected.randomizer);
+        assert_eq!(board.zobrist_hash(), expected.zobrist_hash());
+    }
+
+    #[test]
+    fn undo_restores_the_board() {
+        let original: Board<ColoredRow> = "
+            queue: IOZT
+            bag: LJS
+            combo: 1
+            b2b: 2
+            #.........
+            ....######
+        "
+        .parse()
+        .unwrap();
+        let place = |kind, x, y| FallingPiece {
+            kind: PieceState(kind, RotationState::North),
+            x,
+            y,
+            tspin: TspinStatus::None,
+        };
+        // The Z is placed from behind the O, which is held, and an L is dealt while placing it.
+        // The J is dealt to an empty queue and taken right away.
+        let placements = [
+            (place(Piece::I, 1, 0), None),
+            (place(Piece::Z, 5, 4), Some(Piece::L)),
+            (place(Piece::T, 2, 8), None),
+            (place(Piece::L, 7, 12), None),
+            (place(Piece::J, 2, 14), Some(Piece::J)),
+        ];
+
+        let mut board = original.clone();
+        let mut history = vec![];
+        for &(piece, dealt) in &placements {
+            let before = board.clone();
+            let (lock, undo) = board.lock_piece_undoable(piece);
+            if let Some(p) = dealt {
+                board.add_next_piece(p);
+            }
+            let mut next = board.advance_queue().unwrap();
+            if next != piece.kind.0 {
+                assert_eq!(board.hold(next), None);
+                next = board.advance_queue().unwrap();
+            }
+            assert_eq!(next, piece.kind.0);
+            history.push((before, lock, undo));
+        }
+        assert_eq!(history[0].1.cleared_lines.len(), 1);
+        assert_eq!(board.combo, 0);
+        assert_eq!(board.b2b_chain, 0);
+        assert_eq!(board.hold_piece, Some(Piece::O));
+        assert_eq!(board.next_queue().count(), 0);
+
+        while let Some((before, _, undo)) = history.pop() {
+            board.undo(undo);
+            assert_same(&board, &before);
+        }
+        assert_same(&board, &original);
+    }
 }
@@ -0,0 +1,270 @@
+use std::collections::{HashSet, VecDeque};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{Board, Controller, FallingPiece, FlipKicks, RotationSystem, Row, TspinStatus};
+
+/// A key press, as counted for finesse. Holding a shift key until the piece hits the wall counts
+/// as a single press.
+#[derive(Copy, Clone, Debug, Hash, Eq, PartialEq, Serialize, Deserialize)]
+pub enum FinesseKey {
+    Left,
+    Right,
+    DasLeft,
+    DasRight,
+    Cw,
+    Ccw,
+    Flip,
+}
+
+/// A piece that was placed using more key presses than necessary.
+#[derive(Clone, Debug, Hash, Eq, PartialEq, Serialize, Deserialize)]
+pub struct FinesseFault {
+    pub piece: FallingPiece,
+    /// The number of key presses actually used to place the piece.
+    pub presses: u32,
+    /// One of the shortest key sequences that places the piece.
+    pub optimal: Vec<FinesseKey>,
+}
+
+/// Counts the key presses used to place each piece and reports finesse faults.
+///
+/// Presses made between placing one piece and placing the next are counted against the next
+/// piece. Holding resets the count.
+#[derive(Clone, Debug)]
+pub struct FinesseChecker {
+    rotation_system: RotationSystem,
+    flip_kicks: Option<FlipKicks>,
+    prev: Controller,
+    presses: u32,
+    spawned: Option<(Board, FallingPiece)>,
+}
+
+impl FinesseKey {
+    fn apply<R: Row>(
+        self,
+        piece: &mut FallingPiece,
+        board: &Board<R>,
+        rotation_system: RotationSystem,
+        flip_kicks: Option<FlipKicks>,
+    ) -> bool {
+        match self {
+            FinesseKey::Left => piece.shift(board, -1, 0),
+            FinesseKey::Right => piece.shift(board, 1, 0),
+            FinesseKey::DasLeft => {
+                let moved = piece.shift(board, -1, 0);
+                while piece.shift(board, -1, 0) {}
+                moved
+            }
+            FinesseKey::DasRight => {
+                let moved = piece.shift(board, 1, 0);
+                while piece.shift(board, 1, 0) {}
+                moved
+            }
+            FinesseKey::Cw => piece.cw(board, rotation_system),
+            FinesseKey::Ccw => piece.ccw(board, rotation_system),
+            FinesseKey::Flip => match flip_kicks {
+                Some(flip_kicks) => piece.flip(board, rotation_system, flip_kicks),
+                None => false,
+            },
+        }
+    }
+}
+
+/// Finds the shortest key sequence that moves the `spawned` piece so that hard dropping it places
+/// it at `target`.
+///
+/// Only movement above the stack is considered, so this returns `None` for placements that need
+/// soft drop, such as tucks and spins.
+pub fn finesse<R: Row>(
+    board: &Board<R>,
+    spawned: FallingPiece,
+    target: FallingPiece,
+    rotation_system: RotationSystem,
+    flip_kicks: Option<FlipKicks>,
+) -> Option<Vec<FinesseKey>> {
+    use FinesseKey::*;
+    const KEYS: [FinesseKey; 7] = [Left, Right, DasLeft, DasRight, Cw, Ccw, Flip];
+
+    let mut checked = HashSet::new();
+    let mut queue = VecDeque::new();
+    checked.insert(spawned);
+    queue.push_back((spawned, vec![]));
+
+    while let Some((piece, keys)) = queue.pop_front() {
+        let mut dropped = piece;
+        dropped.sonic_drop(board);
+        if dropped.same_location(&target) {
+            return Some(keys);
+        }
+
+        for &key in &KEYS {
+            let mut moved = piece;
+            if key.apply(&mut moved, board, rotation_system, flip_kicks) {
+                // Spin status doesn't matter above the stack, and would stop us from noticing
+                // that we've already been here.
+                moved.tspin = TspinStatus::None;
+                if checked.insert(moved) {
+                    let mut keys = keys.clone();
+                    keys.push(key);
+                    queue.push_back((moved, keys));
+                }
+            }
+        }
+    }
+
+    None
+}
+
+impl FinesseChecker {
+    pub fn new(rotation_system: RotationSystem, flip_kicks: Option<FlipKicks>) -> Self {
+        FinesseChecker {
+            rotation_system,
+            flip_kicks,
+            prev: Controller::default(),
+            presses: 0,
+            spawned: None,
+        }
+    }
+
+    /// Counts the movement and rotation keys newly pressed this frame.
+    pub fn update(&mut self, controller: Controller) {
+        let prev = self.prev;
+        self.presses += [
+            controller.left && !prev.left,
+            controller.right && !prev.right,
+            controller.rotate_right && !prev.rotate_right,
+            controller.rotate_left && !prev.rotate_left,
+            controller.rotate_180 && !prev.rotate_180,
+        ]
+        .iter()
+        .filter(|&&pressed| pressed)
+        .count() as u32;
+        self.prev = controller;
+    }
+
+    /// Records the piece that has just spawned and the board it spawned on.
+    pub fn piece_spawned<R: Row>(&mut self, board: &Board<R>, piece: FallingPiece) {
+        self.spawned = Some((board.to_compressed(), piece));
+    }
+
+    /// Discards the presses used to move a piece that was put into hold.
+    pub fn piece_held(&mut self) {
+        self.spawned = None;
+        self.presses = 0;
+    }
+
+    /// Checks the presses used to place the piece at `placed`, returning the fault if more were
+    /// used than necessary.
+    pub fn piece_placed(&mut self, placed: FallingPiece) -> Option<FinesseFault> {
+        let presses = std::mem::replace(&mut self.presses, 0);
+        let (board, spawned) = self.spawned.take()?;
+        let optimal = finesse(
+            &board,
+            spawned,
+            placed,
+            self.rotation_system,
+            self.flip_kicks,
+        )?;
+        if presses > optimal.len() as u32 {
+            Some(FinesseFault {
+                piece: placed,
+                presses,
+                optimal,
+            })
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Piece, PieceState, RotationState, SpawnRule};
+
+    fn spawn(board: &Board, piece: Piece) -> FallingPiece {
+        SpawnRule::Row19Or20
+            .spawn(piece, board, RotationSystem::Srs)
+            .unwrap()
+    }
+
+    fn target(board: &Board, piece: Piece, rotation: RotationState, x: i32) -> FallingPiece {
+        let mut target = FallingPiece {
+            kind: PieceState(piece, rotation),
+            x,
+            y: 20,
+            tspin: TspinStatus::None,
+        };
+        target.sonic_drop(board);
+        target
+    }
+
+    #[test]
+    fn open_board() {
+        use FinesseKey::*;
+        use RotationState::*;
+        let board = Board::new();
+        let cases: &[(Piece, RotationState, i32, Option<FlipKicks>, &[FinesseKey])] = &[
+            (Piece::O, North, 4, None, &[]),
+            (Piece::T, North, 1, None, &[DasLeft]),
+            (Piece::T, North, 2, None, &[Left, Left]),
+            (Piece::T, East, 0, None, &[Cw, DasLeft]),
+            (Piece::T, South, 4, None, &[Cw, Cw]),
+            (Piece::T, South, 4, Some(FlipKicks::Tetrio), &[Flip]),
+            (Piece::I, West, 9, None, &[Cw, DasRight]),
+            (Piece::L, West, 9, None, &[Ccw, DasRight]),
+        ];
+        for &(piece, rotation, x, flip_kicks, expected) in cases {
+            let keys = finesse(
+                &board,
+                spawn(&board, piece),
+                target(&board, piece, rotation, x),
+                RotationSystem::Srs,
+                flip_kicks,
+            );
+            assert_eq!(
+                keys.as_deref(),
+                Some(expected),
+                "{:?} {:?} {}",
+                piece,
+                rotation,
+                x
+            );
+        }
+    }
+
+    #[test]
+    fn checker_reports_faults() {
+        let board = Board::new();
+        let mut checker = FinesseChecker::new(RotationSystem::Srs, None);
+        let left = Controller {
+            left: true,
+            ..Controller::default()
+        };
+
+        // three taps to the wall instead of holding left
+        checker.piece_spawned(&board, spawn(&board, Piece::T));
+        for _ in 0..3 {
+            checker.update(left);
+            checker.update(Controller::default());
+        }
+        let placed = target(&board, Piece::T, RotationState::North, 1);
+        assert_eq!(
+            checker.piece_placed(placed),
+            Some(FinesseFault {
+                piece: placed,
+                presses: 3,
+                optimal: vec![FinesseKey::DasLeft],
+            })
+        );
+
+        // holding left is a single press
+        checker.piece_spawned(&board, spawn(&board, Piece::T));
+        for _ in 0..10 {
+            checker.update(left);
+        }
+        checker.update(Controller::default());
+        assert_eq!(checker.piece_placed(placed), None);
+    }
+}
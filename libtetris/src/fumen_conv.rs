@@ -71,3 +71,151 @@ impl From<RotationState> for fumen::RotationState {
         }
     }
 }
+
+impl From<CellColor> for fumen::CellColor {
+    fn from(v: CellColor) -> fumen::CellColor {
+        match v {
+            CellColor::I => fumen::CellColor::I,
+            CellColor::O => fumen::CellColor::O,
+            CellColor::T => fumen::CellColor::T,
+            CellColor::L => fumen::CellColor::L,
+            CellColor::J => fumen::CellColor::J,
+            CellColor::S => fumen::CellColor::S,
+            CellColor::Z => fumen::CellColor::Z,
+            CellColor::Garbage | CellColor::Unclearable => fumen::CellColor::Grey,
+            CellColor::Empty => fumen::CellColor::Empty,
+        }
+    }
+}
+
+impl<R: Row> Board<R> {
+    /// Adds a page showing this board and an optional piece to the fumen.
+    pub fn add_fumen_page(
+        &self,
+        fumen: &mut fumen::Fumen,
+        piece: Option<FallingPiece>,
+        comment: String,
+    ) {
+        let page = fumen.add_page();
+        for (y, row) in page.field.iter_mut().enumerate() {
+            for (x, cell) in row.iter_mut().enumerate() {
+                // rows above the ceiling are solid on the board, but fumen has no ceiling
+                *cell = match y < self.height() as usize {
+                    true => self.get_row(y as i32).cell_color(x).into(),
+                    false => fumen::CellColor::Empty,
+                };
+            }
+        }
+        page.piece = piece.map(Into::into);
+        page.comment = Some(comment);
+    }
+
+    /// Encodes this board followed by a sequence of placements as a fumen.
+    ///
+    /// Each placement gets a page showing it on the board left by the previous placements,
+    /// commented with the line clear it makes. The first page also lists the hold piece and queue.
+    /// If there are no placements, the fumen has a single page showing the board.
+    pub fn to_fumen(&self, placements: impl IntoIterator<Item = FallingPiece>) -> fumen::Fumen {
+        let mut fumen = fumen::Fumen::default();
+        let mut board = self.clone();
+        let mut comment = self.fumen_queue_comment();
+        for placement in placements {
+            let before = board.clone();
+            let lock = board.lock_piece(placement);
+            let lock_comment = lock.fumen_comment();
+            if !comment.is_empty() && !lock_comment.is_empty() {
+                comment.push_str(", ");
+            }
+            comment.push_str(&lock_comment);
+            before.add_fumen_page(&mut fumen, Some(placement), comment);
+            comment = String::new();
+        }
+        if fumen.pages.is_empty() {
+            self.add_fumen_page(&mut fumen, None, comment);
+        }
+        fumen
+    }
+
+    fn fumen_queue_comment(&self) -> String {
+        let mut comment = String::new();
+        if let Some(hold) = self.hold_piece {
+            comment.push_str(&format!("Hold: {:?}", hold));
+        }
+        let queue: String = self.next_queue().map(|p| format!("{:?}", p)).collect();
+        if !queue.is_empty() {
+            if !comment.is_empty() {
+                comment.push_str(", ");
+            }
+            comment.push_str(&format!("Queue: {}", queue));
+        }
+        comment
+    }
+}
+
+impl LockResult {
+    /// Describes the placement for fumen comments, e.g. "B2B T-Spin Double, 3 combo, 5 lines".
+    pub fn fumen_comment(&self) -> String {
+        let mut comment = String::new();
+        if self.perfect_clear {
+            comment.push_str("Perfect Clear ");
+        }
+        if self.b2b {
+            comment.push_str("B2B ");
+        }
+        comment.push_str(self.placement_kind.name());
+        let mut comment = comment.trim().to_owned();
+        if let Some(combo) = self.combo.filter(|&c| c > 0) {
+            comment.push_str(&format!(", {} combo", combo));
+        }
+        if self.garbage_sent > 0 {
+            comment.push_str(&format!(", {} lines", self.garbage_sent));
+        }
+        comment
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn place(kind: Piece, x: i32, y: i32) -> FallingPiece {
+        FallingPiece {
+            kind: PieceState(kind, RotationState::North),
+            x,
+            y,
+            tspin: TspinStatus::None,
+        }
+    }
+
+    #[test]
+    fn board_and_plan_to_fumen() {
+        let board: Board<ColoredRow> = "
+            hold: T
+            queue: IO
+            L.........
+            ....JJJSSZ
+        "
+        .parse()
+        .unwrap();
+        let plan = [place(Piece::I, 1, 0), place(Piece::O, 4, 0)];
+        let fumen = board.to_fumen(plan.iter().copied());
+        assert_eq!(
+            fumen.encode(),
+            "v115@RhglMei0R4AtJexOYqAI3MoDlsCSASYtSAyE88AwZ4rD14UABBoo2AJX88ADoo2ATOZyDsoBAAvhATrQAA"
+        );
+
+        let decoded = fumen::Fumen::decode(&fumen.encode()).unwrap();
+        assert_eq!(decoded.pages.len(), 2);
+        let first = &decoded.pages[0];
+        assert_eq!(first.piece, Some(plan[0].into()));
+        assert_eq!(first.comment.as_deref(), Some("Hold: T, Queue: IO, Single"));
+        assert_eq!(first.field[0][4], fumen::CellColor::J);
+        assert_eq!(first.field[1][0], fumen::CellColor::L);
+        // the second page shows the board after the single is cleared
+        let second = &decoded.pages[1];
+        assert_eq!(second.piece, Some(plan[1].into()));
+        assert_eq!(second.comment.as_deref(), Some(""));
+        assert_eq!(second.field[0][0], fumen::CellColor::L);
+        assert_eq!(second.field[0][4], fumen::CellColor::Empty);
+    }
+}
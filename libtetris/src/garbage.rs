@@ -0,0 +1,130 @@
+use ordered_float::NotNan;
+use rand::seq::index;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::{Board, Row};
+
+/// Describes where the holes in incoming garbage are placed.
+///
+/// Holes move to new columns with `attack_messiness` chance at the start of each attack, and with
+/// `row_messiness` chance before each row. Tetris 99 style garbage, where each attack is a clean
+/// block with its own hole, has an `attack_messiness` of 1 and a `row_messiness` of 0. TETR.IO's
+/// garbage messiness setting corresponds to `row_messiness`.
+#[derive(Copy, Clone, Debug, Hash, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GarbageRules {
+    /// The number of holes in each row.
+    pub holes: u32,
+    pub row_messiness: NotNan<f64>,
+    pub attack_messiness: NotNan<f64>,
+    /// Holes that move never move to a column that was a hole in the row below.
+    pub avoid_previous: bool,
+}
+
+impl GarbageRules {
+    /// Each attack is a block of rows sharing one hole.
+    pub fn clean() -> Self {
+        GarbageRules {
+            holes: 1,
+            row_messiness: NotNan::new(0.0).unwrap(),
+            attack_messiness: NotNan::new(1.0).unwrap(),
+            avoid_previous: false,
+        }
+    }
+
+    /// Each row's hole moves to a new column with `messiness` chance.
+    pub fn messy(messiness: f64) -> Self {
+        GarbageRules {
+            row_messiness: NotNan::new(messiness).unwrap(),
+            ..GarbageRules::clean()
+        }
+    }
+
+    /// Cheese, where no two rows in a row have a hole in the same column.
+    pub fn cheese() -> Self {
+        GarbageRules {
+            row_messiness: NotNan::new(1.0).unwrap(),
+            avoid_previous: true,
+            ..GarbageRules::clean()
+        }
+    }
+}
+
+impl Default for GarbageRules {
+    fn default() -> Self {
+        GarbageRules::messy(0.3)
+    }
+}
+
+/// Generates rows of garbage according to a set of `GarbageRules`.
+///
+/// The generator only draws randomness from the rng it's given, so garbage is reproducible from
+/// the seed of that rng.
+#[derive(Clone, Debug)]
+pub struct GarbageGenerator {
+    rules: GarbageRules,
+    width: usize,
+    holes: Vec<usize>,
+}
+
+impl GarbageGenerator {
+    pub fn new(rules: GarbageRules, width: usize) -> Self {
+        GarbageGenerator {
+            rules,
+            width,
+            holes: vec![],
+        }
+    }
+
+    /// Generates the holes of each row of an attack of `rows` rows, in the order they should be
+    /// added to the board.
+    pub fn generate(&mut self, rows: u32, rng: &mut impl Rng) -> Vec<Vec<usize>> {
+        if rows == 0 {
+            return vec![];
+        }
+        if self.holes.is_empty() || rng.gen_bool(self.rules.attack_messiness.into_inner()) {
+            self.move_holes(rng);
+        }
+        let mut garbage = vec![];
+        for _ in 0..rows {
+            if rng.gen_bool(self.rules.row_messiness.into_inner()) {
+                self.move_holes(rng);
+            }
+            garbage.push(self.holes.clone());
+        }
+        garbage
+    }
+
+    /// Adds an attack of `rows` rows of garbage to the board. Returns the holes of each row and
+    /// whether the player topped out.
+    pub fn add_to<R: Row>(
+        &mut self,
+        board: &mut Board<R>,
+        rows: u32,
+        rng: &mut impl Rng,
+    ) -> (Vec<Vec<usize>>, bool) {
+        let garbage = self.generate(rows, rng);
+        let mut dead = false;
+        for holes in &garbage {
+            dead |= board.add_garbage_with_holes(holes);
+        }
+        (garbage, dead)
+    }
+
+    fn move_holes(&mut self, rng: &mut impl Rng) {
+        let holes = (self.rules.holes as usize).max(1).min(self.width - 1);
+        let mut columns: Vec<_> = (0..self.width).collect();
+        if self.rules.avoid_previous && self.width - self.holes.len() >= holes {
+            columns.retain(|c| !self.holes.contains(c));
+        }
+        self.holes = if holes == 1 {
+            vec![columns[rng.gen_range(0, columns.len())]]
+        } else {
+            index::sample(rng, columns.len(), holes)
+                .into_iter()
+                .map(|i| columns[i])
+                .collect()
+        };
+    }
+}
@@ -1,18 +1,38 @@
+mod attack;
 mod board;
+mod finesse;
+mod garbage;
 mod lock_data;
 mod moves;
+mod perft;
 mod piece;
+mod placements;
+mod randomizer;
+mod rotation;
+mod text;
+mod top_out;
+mod zobrist;
 
 #[cfg(feature = "fumen")]
 mod fumen_conv;
+#[cfg(feature = "fumen")]
+pub use fumen;
 
 #[cfg(feature = "pcf")]
 mod pcf_conv;
 
+pub use attack::*;
 pub use board::*;
+pub use finesse::*;
+pub use garbage::*;
 pub use lock_data::*;
 pub use moves::*;
+pub use perft::*;
 pub use piece::*;
+pub use randomizer::*;
+pub use rotation::*;
+pub use text::*;
+pub use top_out::*;
 
 #[derive(Copy, Clone, Debug, Default, Hash, Eq, PartialEq)]
 pub struct Controller {
@@ -20,6 +40,7 @@ pub struct Controller {
     pub right: bool,
     pub rotate_right: bool,
     pub rotate_left: bool,
+    pub rotate_180: bool,
     pub soft_drop: bool,
     pub hard_drop: bool,
     pub hold: bool,
@@ -28,7 +49,8 @@ pub struct Controller {
 impl serde::Serialize for Controller {
     fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
         serializer.serialize_u8(
-            (self.left as u8) << 1
+            (self.rotate_180 as u8)
+                | (self.left as u8) << 1
                 | (self.right as u8) << 2
                 | (self.rotate_left as u8) << 3
                 | (self.rotate_right as u8) << 4
@@ -49,6 +71,7 @@ impl<'de> serde::Deserialize<'de> for Controller {
             }
             fn visit_u64<E: serde::de::Error>(self, v: u64) -> Result<Controller, E> {
                 Ok(Controller {
+                    rotate_180: v & 1 != 0,
                     left: (v >> 1) & 1 != 0,
                     right: (v >> 2) & 1 != 0,
                     rotate_left: (v >> 3) & 1 != 0,
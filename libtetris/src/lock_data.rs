@@ -1,13 +1,17 @@
 use arrayvec::ArrayVec;
 use serde::{Deserialize, Serialize};
 
-use crate::piece::TspinStatus;
+use crate::piece::{Piece, TspinStatus};
 
 #[derive(Clone, Debug, Eq, PartialEq, Hash, Default, Serialize, Deserialize)]
 pub struct LockResult {
     pub placement_kind: PlacementKind,
     pub locked_out: bool,
     pub b2b: bool,
+    /// The number of consecutive hard line clears after this placement.
+    pub b2b_chain: u32,
+    /// The lines released by breaking a back-to-back chain, included in `garbage_sent`.
+    pub surge: u32,
     pub perfect_clear: bool,
     pub combo: Option<u32>,
     pub garbage_sent: u32,
@@ -24,43 +28,58 @@ pub enum PlacementKind {
     MiniTspin,
     MiniTspin1,
     MiniTspin2,
+    MiniTspin3,
     Tspin,
     Tspin1,
     Tspin2,
     Tspin3,
+    MiniSpin,
+    MiniSpin1,
+    MiniSpin2,
+    MiniSpin3,
+    Spin,
+    Spin1,
+    Spin2,
+    Spin3,
 }
 
 impl PlacementKind {
-    /// The amount of garbage this clear kind normally sends.
-    pub fn garbage(self) -> u32 {
-        use PlacementKind::*;
-        match self {
-            None | MiniTspin | Tspin | Clear1 | MiniTspin1 => 0,
-            Clear2 | MiniTspin2 => 1,
-            Clear3 | Tspin1 => 2,
-            Clear4 | Tspin2 => 4,
-            Tspin3 => 6,
-        }
-    }
-
     /// Whether or not this placement does back-to-backs.
     pub fn is_hard(self) -> bool {
         use PlacementKind::*;
         match self {
-            Clear4 | MiniTspin | MiniTspin1 | MiniTspin2 | Tspin | Tspin1 | Tspin2 | Tspin3 => true,
-            _ => false,
+            None | Clear1 | Clear2 | Clear3 => false,
+            _ => true,
         }
     }
 
     /// Whether or not this placement did a line clear.
     pub fn is_clear(self) -> bool {
         match self {
-            PlacementKind::None | PlacementKind::MiniTspin | PlacementKind::Tspin => false,
+            PlacementKind::None
+            | PlacementKind::MiniTspin
+            | PlacementKind::Tspin
+            | PlacementKind::MiniSpin
+            | PlacementKind::Spin => false,
             _ => true,
         }
     }
 
-    pub(crate) fn get(cleared: usize, tspin: TspinStatus) -> Self {
+    pub(crate) fn get(cleared: usize, tspin: TspinStatus, piece: Piece) -> Self {
+        if piece != Piece::T && tspin != TspinStatus::None {
+            return match (cleared, tspin) {
+                (0, TspinStatus::Mini) => PlacementKind::MiniSpin,
+                (0, _) => PlacementKind::Spin,
+                (1, TspinStatus::Mini) => PlacementKind::MiniSpin1,
+                (1, _) => PlacementKind::Spin1,
+                (2, TspinStatus::Mini) => PlacementKind::MiniSpin2,
+                (2, _) => PlacementKind::Spin2,
+                (3, TspinStatus::Mini) => PlacementKind::MiniSpin3,
+                (3, _) => PlacementKind::Spin3,
+                // Vertical I spins clearing 4 lines are just tetrises.
+                _ => PlacementKind::Clear4,
+            };
+        }
         match (cleared, tspin) {
             (0, TspinStatus::None) => PlacementKind::None,
             (0, TspinStatus::Mini) => PlacementKind::MiniTspin,
@@ -72,7 +91,9 @@ impl PlacementKind {
             (2, TspinStatus::Mini) => PlacementKind::MiniTspin2,
             (2, _) => PlacementKind::Tspin2,
             (3, TspinStatus::None) => PlacementKind::Clear3,
-            (3, TspinStatus::Mini) => unreachable!(),
+            // SRS only reaches triples through the upgrading TST kick, but 180 kicks, other
+            // rotation systems and the all-mini spin rule can leave a triple as a mini.
+            (3, TspinStatus::Mini) => PlacementKind::MiniTspin3,
             (3, _) => PlacementKind::Tspin3,
             (4, TspinStatus::None) => PlacementKind::Clear4,
             _ => unreachable!(),
@@ -89,10 +110,19 @@ impl PlacementKind {
             PlacementKind::MiniTspin => "Mini T-Spin",
             PlacementKind::MiniTspin1 => "Mini T-Spin Single",
             PlacementKind::MiniTspin2 => "Mini T-Spin Double",
+            PlacementKind::MiniTspin3 => "Mini T-Spin Triple",
             PlacementKind::Tspin => "T-Spin",
             PlacementKind::Tspin1 => "T-Spin Single",
             PlacementKind::Tspin2 => "T-Spin Double",
             PlacementKind::Tspin3 => "T-Spin Triple",
+            PlacementKind::MiniSpin => "Mini Spin",
+            PlacementKind::MiniSpin1 => "Mini Spin Single",
+            PlacementKind::MiniSpin2 => "Mini Spin Double",
+            PlacementKind::MiniSpin3 => "Mini Spin Triple",
+            PlacementKind::Spin => "Spin",
+            PlacementKind::Spin1 => "Spin Single",
+            PlacementKind::Spin2 => "Spin Double",
+            PlacementKind::Spin3 => "Spin Triple",
         }
     }
 
@@ -106,10 +136,19 @@ impl PlacementKind {
             PlacementKind::MiniTspin => "ts",
             PlacementKind::MiniTspin1 => "tss",
             PlacementKind::MiniTspin2 => "tsd",
+            PlacementKind::MiniTspin3 => "tst",
             PlacementKind::Tspin => "TS",
             PlacementKind::Tspin1 => "TSS",
             PlacementKind::Tspin2 => "TSD",
             PlacementKind::Tspin3 => "TST",
+            PlacementKind::MiniSpin => "sp",
+            PlacementKind::MiniSpin1 => "sps",
+            PlacementKind::MiniSpin2 => "spd",
+            PlacementKind::MiniSpin3 => "spt",
+            PlacementKind::Spin => "SP",
+            PlacementKind::Spin1 => "SPS",
+            PlacementKind::Spin2 => "SPD",
+            PlacementKind::Spin3 => "SPT",
         }
     }
 }
@@ -120,20 +159,32 @@ impl Default for PlacementKind {
     }
 }
 
-pub const COMBO_GARBAGE: [u32; 12] = [
-    0, 0, // 0, 1 combo
-    1, 1, // 2, 3 combo
-    2, 2, // 4, 5 combo
-    3, 3, // 6, 7 combo
-    4, 4, 4, // 8, 9, 10 combo
-    5, // 11+ combo
-];
-
+/// Time is measured in ticks, at 60 ticks per second. Rates are measured up to the last
+/// placement recorded with `update_at`.
 #[derive(Copy, Clone, Debug, Eq, PartialEq, Default, Hash, Serialize, Deserialize)]
+#[serde(default)]
 pub struct Statistics {
     pub pieces: u64,
     pub lines: u64,
     pub attack: u64,
+    pub ticks: u64,
+    pub holds: u64,
+
+    pub garbage_received: u64,
+    pub garbage_cleared: u64,
+    /// Garbage rows still on the board. Garbage is always the bottom rows of the board.
+    pub garbage_on_board: u64,
+    /// Pieces placed while there was garbage on the board.
+    pub downstack_pieces: u64,
+    /// Pieces placed while there was no garbage on the board.
+    pub upstack_pieces: u64,
+
+    pub clear_attack: u64,
+    pub tspin_attack: u64,
+    pub spin_attack: u64,
+    /// All attack sent by perfect clears, other than surges.
+    pub perfect_clear_attack: u64,
+    pub surge_attack: u64,
 
     pub singles: u64,
     pub doubles: u64,
@@ -146,16 +197,62 @@ pub struct Statistics {
     pub mini_tspin_zeros: u64,
     pub mini_tspin_singles: u64,
     pub mini_tspin_doubles: u64,
+    pub mini_tspin_triples: u64,
+    pub spin_zeros: u64,
+    pub spin_singles: u64,
+    pub spin_doubles: u64,
+    pub spin_triples: u64,
+    pub mini_spin_zeros: u64,
+    pub mini_spin_singles: u64,
+    pub mini_spin_doubles: u64,
+    pub mini_spin_triples: u64,
     pub perfect_clears: u64,
     pub max_combo: u64,
+    pub max_b2b_chain: u64,
 }
 
 impl Statistics {
+    /// Records a placement made at `ticks` since the start of the game.
+    pub fn update_at(&mut self, l: &LockResult, ticks: u64) {
+        self.ticks = ticks;
+        self.update(l);
+    }
+
     pub fn update(&mut self, l: &LockResult) {
         self.attack += l.garbage_sent as u64;
         self.lines += l.cleared_lines.len() as u64;
         self.pieces += 1;
 
+        if self.garbage_on_board > 0 {
+            self.downstack_pieces += 1;
+        } else {
+            self.upstack_pieces += 1;
+        }
+        let garbage_cleared = l
+            .cleared_lines
+            .iter()
+            .filter(|&&y| (y as u64) < self.garbage_on_board)
+            .count() as u64;
+        self.garbage_cleared += garbage_cleared;
+        self.garbage_on_board -= garbage_cleared;
+
+        let surge = l.surge as u64;
+        let attack = (l.garbage_sent as u64).saturating_sub(surge);
+        self.surge_attack += surge;
+        use PlacementKind::*;
+        if l.perfect_clear {
+            self.perfect_clear_attack += attack;
+        } else {
+            match l.placement_kind {
+                MiniTspin | MiniTspin1 | MiniTspin2 | MiniTspin3 | Tspin | Tspin1 | Tspin2
+                | Tspin3 => self.tspin_attack += attack,
+                MiniSpin | MiniSpin1 | MiniSpin2 | MiniSpin3 | Spin | Spin1 | Spin2 | Spin3 => {
+                    self.spin_attack += attack
+                }
+                _ => self.clear_attack += attack,
+            }
+        }
+
         if l.perfect_clear {
             self.perfect_clears += 1;
         }
@@ -164,6 +261,7 @@ impl Statistics {
                 self.max_combo = combo as u64;
             }
         }
+        self.max_b2b_chain = self.max_b2b_chain.max(l.b2b_chain as u64);
 
         match l.placement_kind {
             PlacementKind::None => {}
@@ -178,6 +276,60 @@ impl Statistics {
             PlacementKind::MiniTspin => self.mini_tspin_zeros += 1,
             PlacementKind::MiniTspin1 => self.mini_tspin_singles += 1,
             PlacementKind::MiniTspin2 => self.mini_tspin_doubles += 1,
+            PlacementKind::MiniTspin3 => self.mini_tspin_triples += 1,
+            PlacementKind::Spin => self.spin_zeros += 1,
+            PlacementKind::Spin1 => self.spin_singles += 1,
+            PlacementKind::Spin2 => self.spin_doubles += 1,
+            PlacementKind::Spin3 => self.spin_triples += 1,
+            PlacementKind::MiniSpin => self.mini_spin_zeros += 1,
+            PlacementKind::MiniSpin1 => self.mini_spin_singles += 1,
+            PlacementKind::MiniSpin2 => self.mini_spin_doubles += 1,
+            PlacementKind::MiniSpin3 => self.mini_spin_triples += 1,
         }
     }
+
+    /// Records rows of garbage being added to the bottom of the board.
+    pub fn garbage_added(&mut self, rows: u64) {
+        self.garbage_received += rows;
+        self.garbage_on_board += rows;
+    }
+
+    pub fn hold_used(&mut self) {
+        self.holds += 1;
+    }
+
+    pub fn seconds(&self) -> f64 {
+        self.ticks as f64 / 60.0
+    }
+
+    /// Pieces per second.
+    pub fn pps(&self) -> f64 {
+        per(self.pieces as f64, self.seconds())
+    }
+
+    /// Attack per minute.
+    pub fn apm(&self) -> f64 {
+        per(self.attack as f64 * 60.0, self.seconds())
+    }
+
+    /// Attack per piece.
+    pub fn app(&self) -> f64 {
+        per(self.attack as f64, self.pieces as f64)
+    }
+
+    /// Attack and garbage cleared per 100 seconds, as TETR.IO's versus score.
+    pub fn vs_score(&self) -> f64 {
+        per(
+            (self.attack + self.garbage_cleared) as f64 * 100.0,
+            self.seconds(),
+        )
+    }
+}
+
+fn per(amount: f64, over: f64) -> f64 {
+    if over == 0.0 {
+        0.0
+    } else {
+        amount / over
+    }
 }
@@ -4,7 +4,11 @@ use std::collections::{BinaryHeap, HashMap, HashSet};
 use arrayvec::ArrayVec;
 use serde::{Deserialize, Serialize};
 
-use crate::{Board, FallingPiece, Piece, PieceMovement, PieceState, RotationState, TspinStatus};
+use crate::placements;
+use crate::{
+    Board, FallingPiece, FlipKicks, Piece, PieceMovement, PieceState, RotationState,
+    RotationSystem, TspinStatus,
+};
 
 #[derive(Clone, Debug, Eq, PartialEq, Hash, Serialize, Deserialize)]
 pub struct InputList {
@@ -31,6 +35,106 @@ pub enum MovementMode {
     ZeroGComplete,
     TwentyG,
     HardDropOnly,
+    /// Hard drops, plus soft dropping and then shifting along the stack. Pieces can't rotate
+    /// after being soft dropped.
+    HardDropAndTuck,
+}
+
+/// How many frames movements take, used to estimate the time a placement's inputs take.
+#[derive(Copy, Clone, Debug, Hash, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MovementCosts {
+    /// Frames a shift button must be held before the piece starts auto-shifting.
+    pub delayed_auto_shift: u32,
+    /// Frames between each auto-shift. 0 means the piece moves to the wall instantly.
+    pub auto_repeat_rate: u32,
+    /// Frames it takes to soft drop the piece one cell. 0 means soft drop is instant.
+    pub soft_drop_speed: u32,
+}
+
+impl MovementCosts {
+    /// The time taken by a tapped input. This doesn't depend on the handling settings: a tap
+    /// always takes one frame, plus a frame to release the button when tapping the same input
+    /// twice in a row.
+    pub(crate) fn tap(input: PieceMovement, previous: Option<PieceMovement>) -> u32 {
+        if previous == Some(input) {
+            2
+        } else {
+            1
+        }
+    }
+
+    /// The time between a held shift moving the piece `shifted` cells and moving it once more.
+    fn auto_shift(&self, shifted: u32) -> u32 {
+        if shifted == 1 {
+            self.delayed_auto_shift
+        } else {
+            self.auto_repeat_rate
+        }
+    }
+
+    /// The time taken to soft drop the piece `distance` cells.
+    pub(crate) fn soft_drop(&self, distance: i32) -> u32 {
+        self.soft_drop_speed * distance as u32
+    }
+
+    /// The time taken by tapping each of the inputs in order.
+    fn taps(&self, inputs: &[PieceMovement]) -> u32 {
+        let mut previous = None;
+        let mut time = 0;
+        for &input in inputs {
+            time += Self::tap(input, previous);
+            previous = Some(input);
+        }
+        time
+    }
+
+    /// The time taken by the inputs leading to a starting position. If the position is against
+    /// the wall, the last run of shifts can be done by holding the button instead of tapping it.
+    fn start(&self, inputs: &[PieceMovement], at_wall: bool) -> u32 {
+        let tapped = self.taps(inputs);
+        let last = match inputs.last() {
+            Some(&input) if input == PieceMovement::Left || input == PieceMovement::Right => input,
+            _ => return tapped,
+        };
+        let run = inputs.iter().rev().take_while(|&&m| m == last).count();
+        if !at_wall || run < 2 {
+            return tapped;
+        }
+        let before = &inputs[..inputs.len() - run];
+        let held = self.taps(before)
+            + Self::tap(last, before.last().copied())
+            + (1..run as u32).map(|n| self.auto_shift(n)).sum::<u32>();
+        held.min(tapped)
+    }
+
+    /// The time taken by the inputs leading to a precomputed starting position.
+    pub(crate) fn start_time(
+        &self,
+        board: &Board,
+        place: FallingPiece,
+        inputs: &[PieceMovement],
+        rotation_system: RotationSystem,
+        flip_kicks: Option<FlipKicks>,
+    ) -> u32 {
+        let at_wall = match inputs.last() {
+            Some(&input) => !input.apply(&mut place.clone(), board, rotation_system, flip_kicks),
+            None => false,
+        };
+        self.start(inputs, at_wall)
+    }
+}
+
+impl Default for MovementCosts {
+    fn default() -> Self {
+        // Matches the battle library's default game configuration, which approximates Puyo Puyo
+        // Tetris's versus mode.
+        MovementCosts {
+            delayed_auto_shift: 9,
+            auto_repeat_rate: 2,
+            soft_drop_speed: 2,
+        }
+    }
 }
 
 impl Ord for Placement {
@@ -54,150 +158,205 @@ impl PartialOrd for Placement {
     }
 }
 
-pub fn find_moves(board: &Board, mut spawned: FallingPiece, mode: MovementMode) -> Vec<Placement> {
+pub fn find_moves(
+    board: &Board,
+    spawned: FallingPiece,
+    mode: MovementMode,
+    rotation_system: RotationSystem,
+    flip_kicks: Option<FlipKicks>,
+    costs: MovementCosts,
+) -> Vec<Placement> {
+    // These modes have few enough positions that they don't need the general search.
+    match mode {
+        MovementMode::HardDropOnly => {
+            placements::hard_drop(board, spawned, rotation_system, flip_kicks, costs, false)
+        }
+        MovementMode::HardDropAndTuck => {
+            placements::hard_drop(board, spawned, rotation_system, flip_kicks, costs, true)
+        }
+        MovementMode::TwentyG => placements::twenty_g(board, spawned, rotation_system, flip_kicks),
+        MovementMode::ZeroG | MovementMode::ZeroGComplete => {
+            search(board, spawned, mode, rotation_system, flip_kicks, costs)
+        }
+    }
+}
+
+/// Counts the distinct placements of the piece, for checking move generation against known
+/// results.
+pub fn count_placements(
+    board: &Board,
+    spawned: FallingPiece,
+    mode: MovementMode,
+    rotation_system: RotationSystem,
+    flip_kicks: Option<FlipKicks>,
+) -> usize {
+    find_moves(
+        board,
+        spawned,
+        mode,
+        rotation_system,
+        flip_kicks,
+        MovementCosts::default(),
+    )
+    .len()
+}
+
+/// Whether we know we can reach any column and rotation state without bumping into the terrain
+/// at 0G, so the precomputed starting positions can be used.
+pub(crate) fn precomputed_starts_apply(board: &Board, rotation_system: RotationSystem) -> bool {
+    // The precomputed starting positions assume pieces spawn flat side down and rotate about the
+    // SRS rotation point, which isn't true for ARS. They also assume a 10 wide board with room
+    // for pieces spawned at row 19 to rotate.
+    rotation_system != RotationSystem::Ars
+        && board.width() == 10
+        && board.height() >= 22
+        && board.column_heights().iter().all(|&v| v < 16)
+}
+
+/// The general search for the 0G movement modes.
+fn search(
+    board: &Board,
+    spawned: FallingPiece,
+    mode: MovementMode,
+    rotation_system: RotationSystem,
+    flip_kicks: Option<FlipKicks>,
+    costs: MovementCosts,
+) -> Vec<Placement> {
     let mut locks = HashMap::with_capacity(128);
     let mut checked = HashSet::with_capacity(128);
     let mut check_queue = Vec::with_capacity(64);
     let fast_mode;
 
-    if board.column_heights().iter().all(|&v| v < 16) {
+    if precomputed_starts_apply(board, rotation_system) {
         // We know that we can reach any column and rotation state without bumping into the terrain
         // at 0G here, so we can just grab those starting positions.
-        let starts = match mode {
-            MovementMode::TwentyG => vec![(
-                spawned,
-                InputList {
-                    movements: ArrayVec::new(),
-                    time: 0,
-                },
-            )],
-            _ => zero_g_starts(spawned.kind.0),
-        };
+        let starts = zero_g_starts(spawned.kind.0, flip_kicks.is_some());
         // Fast mode prevents checking a lot of stack movement that is unlikely (but still could)
         // to lead to new placements. Use ZeroGComplete to get these missed positions.
         fast_mode = mode == MovementMode::ZeroG;
         for (mut place, mut inputs) in starts {
+            inputs.time =
+                costs.start_time(board, place, &inputs.movements, rotation_system, flip_kicks);
             let orig_y = place.y;
             place.sonic_drop(board);
             if !fast_mode {
                 checked.insert(place);
             }
-            lock_check(place, &mut locks, inputs.clone());
-            if mode != MovementMode::HardDropOnly {
-                // Initialize stack movement starting positions.
-                inputs.movements.push(PieceMovement::SonicDrop);
-                if mode != MovementMode::TwentyG {
-                    inputs.time += 2 * (orig_y - place.y) as u32;
-                }
-                check_queue.push(Placement {
-                    inputs,
-                    location: place,
-                });
-            }
+            lock_check(board, place, &mut locks, inputs.clone());
+            // Initialize stack movement starting positions.
+            inputs.movements.push(PieceMovement::SonicDrop);
+            inputs.time += costs.soft_drop(orig_y - place.y);
+            check_queue.push(Placement {
+                inputs,
+                location: place,
+            });
         }
     } else {
         fast_mode = false;
-        let mut movements = ArrayVec::new();
-        if mode == MovementMode::TwentyG {
-            spawned.sonic_drop(board);
-            movements.push(PieceMovement::SonicDrop);
-        }
         checked.insert(spawned);
         check_queue.push(Placement {
-            inputs: InputList { movements, time: 0 },
+            inputs: InputList {
+                movements: ArrayVec::new(),
+                time: 0,
+            },
             location: spawned,
         });
     }
 
     let mut check_queue = BinaryHeap::from(check_queue);
+    let ctx = SearchContext {
+        board,
+        rotation_system,
+        flip_kicks,
+        costs,
+        fast_mode,
+    };
 
     while let Some(placement) = check_queue.pop() {
         let moves = placement.inputs;
         let position = placement.location;
         if !moves.movements.is_full() {
             attempt(
-                board,
+                &ctx,
                 &moves,
                 position,
                 &mut checked,
                 &mut check_queue,
-                mode,
-                fast_mode,
                 PieceMovement::Left,
                 false,
             );
             attempt(
-                board,
+                &ctx,
                 &moves,
                 position,
                 &mut checked,
                 &mut check_queue,
-                mode,
-                fast_mode,
                 PieceMovement::Right,
                 false,
             );
 
             if position.kind.0 != Piece::O {
                 attempt(
-                    board,
+                    &ctx,
                     &moves,
                     position,
                     &mut checked,
                     &mut check_queue,
-                    mode,
-                    fast_mode,
                     PieceMovement::Cw,
                     false,
                 );
 
                 attempt(
-                    board,
+                    &ctx,
                     &moves,
                     position,
                     &mut checked,
                     &mut check_queue,
-                    mode,
-                    fast_mode,
                     PieceMovement::Ccw,
                     false,
                 );
+
+                if flip_kicks.is_some() {
+                    attempt(
+                        &ctx,
+                        &moves,
+                        position,
+                        &mut checked,
+                        &mut check_queue,
+                        PieceMovement::Flip,
+                        false,
+                    );
+                }
             }
 
             if mode == MovementMode::ZeroG {
                 attempt(
-                    board,
+                    &ctx,
                     &moves,
                     position,
                     &mut checked,
                     &mut check_queue,
-                    mode,
-                    fast_mode,
                     PieceMovement::Left,
                     true,
                 );
 
                 attempt(
-                    board,
+                    &ctx,
                     &moves,
                     position,
                     &mut checked,
                     &mut check_queue,
-                    mode,
-                    fast_mode,
                     PieceMovement::Right,
                     true,
                 );
             }
 
             attempt(
-                board,
+                &ctx,
                 &moves,
                 position,
                 &mut checked,
                 &mut check_queue,
-                mode,
-                fast_mode,
                 PieceMovement::SonicDrop,
                 false,
             );
@@ -205,14 +364,22 @@ pub fn find_moves(board: &Board, mut spawned: FallingPiece, mode: MovementMode)
 
         let mut position = position;
         position.sonic_drop(board);
-        lock_check(position, &mut locks, moves);
+        lock_check(board, position, &mut locks, moves);
     }
 
-    locks.into_iter().map(|(_, v)| v).collect()
+    locks.into_values().collect()
 }
 
-fn lock_check(piece: FallingPiece, locks: &mut HashMap<FallingPiece, Placement>, moves: InputList) {
-    if piece.cells().iter().all(|&(_, y)| y >= 20) {
+pub(crate) fn lock_check(
+    board: &Board,
+    piece: FallingPiece,
+    locks: &mut HashMap<FallingPiece, Placement>,
+    moves: InputList,
+) {
+    if board
+        .top_out
+        .locked_out(piece.cells().iter().map(|&(_, y)| y))
+    {
         return;
     }
 
@@ -225,161 +392,175 @@ fn lock_check(piece: FallingPiece, locks: &mut HashMap<FallingPiece, Placement>,
     });
 }
 
+/// What the 0G search needs to know to move a piece.
+struct SearchContext<'a> {
+    board: &'a Board,
+    rotation_system: RotationSystem,
+    flip_kicks: Option<FlipKicks>,
+    costs: MovementCosts,
+    fast_mode: bool,
+}
+
 fn attempt(
-    board: &Board,
+    ctx: &SearchContext,
     moves: &InputList,
     mut piece: FallingPiece,
     checked: &mut HashSet<FallingPiece>,
     check_queue: &mut BinaryHeap<Placement>,
-    mode: MovementMode,
-    fast_mode: bool,
     input: PieceMovement,
     repeat: bool,
 ) -> FallingPiece {
+    let board = ctx.board;
     let orig_y = piece.y;
-    if input.apply(&mut piece, board) {
+    if input.apply(&mut piece, board, ctx.rotation_system, ctx.flip_kicks) {
         let mut moves = moves.clone();
         if input == PieceMovement::SonicDrop {
-            // We don't actually know the soft drop speed, but 1 cell every 2 ticks is probably a
-            // decent guess - that's what the battle library's default game configuration has, and
-            // it's also pretty close to Puyo Puyo Tetris's versus mode.
-            moves.time += 2 * (orig_y - piece.y) as u32;
+            moves.time += ctx.costs.soft_drop(orig_y - piece.y);
         } else {
-            moves.time += 1;
-        }
-        if let Some(&m) = moves.movements.last() {
-            if m == input {
-                // Delay from releasing button before pressing it again
-                moves.time += 1;
-            }
+            moves.time += MovementCosts::tap(input, moves.movements.last().copied());
         }
         moves.movements.push(input);
-        while repeat && !moves.movements.is_full() && input.apply(&mut piece, board) {
+        let mut shifted = 1;
+        while repeat
+            && !moves.movements.is_full()
+            && input.apply(&mut piece, board, ctx.rotation_system, ctx.flip_kicks)
+        {
             // This is the DAS left/right case
             moves.movements.push(input);
-            moves.time += 2;
+            moves.time += ctx.costs.auto_shift(shifted);
+            shifted += 1;
         }
-        if !fast_mode || piece.tspin != TspinStatus::None || !board.above_stack(&piece) {
-            // 20G causes instant plummet, but we might actually be playing a high gravity mode
-            // that we're approximating as 20G so we need to add a sonic drop movement to signal to
-            // the input engine that we need the piece to hit the ground before continuing.
-            let drop_input = mode == MovementMode::TwentyG && piece.sonic_drop(board);
-            if checked.insert(piece) {
-                if drop_input && !moves.movements.is_full() {
-                    // We need the sonic drop input for the above reason, but if the move list is
-                    // full this has to be the last move and the input engine should hard drop.
-                    moves.movements.push(PieceMovement::SonicDrop);
-                }
-                if !(mode == MovementMode::HardDropOnly && input == PieceMovement::SonicDrop) {
-                    check_queue.push(Placement {
-                        inputs: moves,
-                        location: piece,
-                    });
-                }
-            }
+        let interesting =
+            !ctx.fast_mode || piece.tspin != TspinStatus::None || !board.above_stack(&piece);
+        if interesting && checked.insert(piece) {
+            check_queue.push(Placement {
+                inputs: moves,
+                location: piece,
+            });
         }
     }
     piece
 }
 
-fn zero_g_starts(p: Piece) -> Vec<(FallingPiece, InputList)> {
+pub(crate) fn zero_g_starts(p: Piece, flip: bool) -> Vec<(FallingPiece, InputList)> {
     use Piece::*;
     use PieceMovement::*;
     use RotationState::*;
+    if flip && p != O {
+        // With a 180 button, reaching south is one input instead of two.
+        let mut starts = zero_g_starts(p, false);
+        starts.retain(|(piece, _)| piece.kind.1 != South);
+        starts.extend(match p {
+            I => vec![
+                start(I, South, 2, &[Left, Flip, Left, Left]),
+                start(I, South, 3, &[Left, Flip, Left]),
+                start(I, South, 4, &[Left, Flip]),
+                start(I, South, 5, &[Flip]),
+                start(I, South, 6, &[Right, Flip]),
+                start(I, South, 7, &[Right, Flip, Right]),
+                start(I, South, 8, &[Right, Flip, Right, Right]),
+            ],
+            _ => vec![
+                start(p, South, 1, &[Left, Flip, Left, Left]),
+                start(p, South, 2, &[Left, Flip, Left]),
+                start(p, South, 3, &[Left, Flip]),
+                start(p, South, 4, &[Flip]),
+                start(p, South, 5, &[Right, Flip]),
+                start(p, South, 6, &[Right, Flip, Right]),
+                start(p, South, 7, &[Right, Flip, Right, Right]),
+                start(p, South, 8, &[Right, Flip, Right, Right, Right]),
+            ],
+        });
+        return starts;
+    }
     match p {
         O => vec![
-            start(O, North, 0, &[Left, Left, Left, Left], 7),
-            start(O, North, 1, &[Left, Left, Left], 5),
-            start(O, North, 2, &[Left, Left], 3),
-            start(O, North, 3, &[Left], 1),
-            start(O, North, 4, &[], 0),
-            start(O, North, 5, &[Right], 1),
-            start(O, North, 6, &[Right, Right], 3),
-            start(O, North, 7, &[Right, Right, Right], 5),
-            start(O, North, 8, &[Right, Right, Right, Right], 7),
+            start(O, North, 0, &[Left, Left, Left, Left]),
+            start(O, North, 1, &[Left, Left, Left]),
+            start(O, North, 2, &[Left, Left]),
+            start(O, North, 3, &[Left]),
+            start(O, North, 4, &[]),
+            start(O, North, 5, &[Right]),
+            start(O, North, 6, &[Right, Right]),
+            start(O, North, 7, &[Right, Right, Right]),
+            start(O, North, 8, &[Right, Right, Right, Right]),
         ],
         I => vec![
-            start(I, North, 1, &[Left, Left, Left], 5),
-            start(I, North, 2, &[Left, Left], 3),
-            start(I, North, 3, &[Left], 1),
-            start(I, North, 4, &[], 0),
-            start(I, North, 5, &[Right], 1),
-            start(I, North, 6, &[Right, Right], 3),
-            start(I, North, 7, &[Right, Right, Right], 5),
-            start(I, West, 0, &[Left, Ccw, Left, Left, Left], 7),
-            start(I, West, 1, &[Left, Ccw, Left, Left], 5),
-            start(I, West, 2, &[Left, Ccw, Left], 3),
-            start(I, West, 3, &[Left, Ccw], 2),
-            start(I, West, 4, &[Ccw], 1),
-            start(I, East, 5, &[Cw], 1),
-            start(I, East, 6, &[Right, Cw], 2),
-            start(I, East, 7, &[Right, Cw, Right], 3),
-            start(I, East, 8, &[Right, Cw, Right, Right], 5),
-            start(I, East, 9, &[Right, Cw, Right, Right, Right], 7),
-            start(I, East, 0, &[Left, Cw, Left, Left, Left, Left], 9),
-            start(I, East, 1, &[Left, Cw, Left, Left, Left], 7),
-            start(I, East, 2, &[Left, Cw, Left, Left], 5),
-            start(I, East, 3, &[Left, Cw, Left], 3),
-            start(I, East, 4, &[Left, Cw], 2),
-            start(I, West, 5, &[Right, Ccw], 2),
-            start(I, West, 6, &[Right, Ccw, Right], 3),
-            start(I, West, 7, &[Right, Ccw, Right, Right], 5),
-            start(I, West, 8, &[Right, Ccw, Right, Right, Right], 7),
-            start(I, West, 9, &[Right, Ccw, Right, Right, Right, Right], 9),
-            start(I, South, 2, &[Left, Cw, Left, Cw, Left], 5),
-            start(I, South, 3, &[Cw, Left, Cw, Left], 4),
-            start(I, South, 4, &[Cw, Left, Cw], 3),
-            start(I, South, 5, &[Cw, Cw], 3),
-            start(I, South, 6, &[Cw, Right, Cw], 3),
-            start(I, South, 7, &[Cw, Right, Cw, Right], 4),
-            start(I, South, 8, &[Right, Cw, Right, Cw, Right], 5),
+            start(I, North, 1, &[Left, Left, Left]),
+            start(I, North, 2, &[Left, Left]),
+            start(I, North, 3, &[Left]),
+            start(I, North, 4, &[]),
+            start(I, North, 5, &[Right]),
+            start(I, North, 6, &[Right, Right]),
+            start(I, North, 7, &[Right, Right, Right]),
+            start(I, West, 0, &[Left, Ccw, Left, Left, Left]),
+            start(I, West, 1, &[Left, Ccw, Left, Left]),
+            start(I, West, 2, &[Left, Ccw, Left]),
+            start(I, West, 3, &[Left, Ccw]),
+            start(I, West, 4, &[Ccw]),
+            start(I, East, 5, &[Cw]),
+            start(I, East, 6, &[Right, Cw]),
+            start(I, East, 7, &[Right, Cw, Right]),
+            start(I, East, 8, &[Right, Cw, Right, Right]),
+            start(I, East, 9, &[Right, Cw, Right, Right, Right]),
+            start(I, East, 0, &[Left, Cw, Left, Left, Left, Left]),
+            start(I, East, 1, &[Left, Cw, Left, Left, Left]),
+            start(I, East, 2, &[Left, Cw, Left, Left]),
+            start(I, East, 3, &[Left, Cw, Left]),
+            start(I, East, 4, &[Left, Cw]),
+            start(I, West, 5, &[Right, Ccw]),
+            start(I, West, 6, &[Right, Ccw, Right]),
+            start(I, West, 7, &[Right, Ccw, Right, Right]),
+            start(I, West, 8, &[Right, Ccw, Right, Right, Right]),
+            start(I, West, 9, &[Right, Ccw, Right, Right, Right, Right]),
+            start(I, South, 2, &[Left, Cw, Left, Cw, Left]),
+            start(I, South, 3, &[Cw, Left, Cw, Left]),
+            start(I, South, 4, &[Cw, Left, Cw]),
+            start(I, South, 5, &[Cw, Cw]),
+            start(I, South, 6, &[Cw, Right, Cw]),
+            start(I, South, 7, &[Cw, Right, Cw, Right]),
+            start(I, South, 8, &[Right, Cw, Right, Cw, Right]),
         ],
         _ => vec![
-            start(p, North, 1, &[Left, Left, Left], 5),
-            start(p, North, 2, &[Left, Left], 3),
-            start(p, North, 3, &[Left], 1),
-            start(p, North, 4, &[], 0),
-            start(p, North, 5, &[Right], 1),
-            start(p, North, 6, &[Right, Right], 3),
-            start(p, North, 7, &[Right, Right, Right], 5),
-            start(p, North, 8, &[Right, Right, Right, Right], 7),
-            start(p, West, 1, &[Left, Ccw, Left, Left], 5),
-            start(p, West, 2, &[Left, Ccw, Left], 3),
-            start(p, West, 3, &[Left, Ccw], 2),
-            start(p, West, 4, &[Ccw], 1),
-            start(p, East, 4, &[Cw], 1),
-            start(p, East, 5, &[Right, Cw], 2),
-            start(p, East, 6, &[Right, Cw, Right], 3),
-            start(p, East, 7, &[Right, Cw, Right, Right], 5),
-            start(p, East, 8, &[Right, Cw, Right, Right, Right], 7),
-            start(p, East, 0, &[Left, Cw, Left, Left, Left], 7),
-            start(p, East, 1, &[Left, Cw, Left, Left], 5),
-            start(p, East, 2, &[Left, Cw, Left], 3),
-            start(p, East, 3, &[Left, Cw], 2),
-            start(p, West, 5, &[Right, Ccw], 2),
-            start(p, West, 6, &[Right, Ccw, Right], 3),
-            start(p, West, 7, &[Right, Ccw, Right, Right], 5),
-            start(p, West, 8, &[Right, Ccw, Right, Right, Right], 7),
-            start(p, West, 9, &[Right, Ccw, Right, Right, Right, Right], 9),
-            start(p, South, 1, &[Left, Cw, Left, Cw, Left], 5),
-            start(p, South, 2, &[Cw, Left, Cw, Left], 4),
-            start(p, South, 3, &[Cw, Left, Cw], 3),
-            start(p, South, 4, &[Cw, Cw], 3),
-            start(p, South, 5, &[Cw, Right, Cw], 3),
-            start(p, South, 6, &[Cw, Right, Cw, Right], 4),
-            start(p, South, 7, &[Right, Cw, Right, Cw, Right], 5),
-            start(p, South, 8, &[Right, Cw, Right, Cw, Right, Right], 7),
+            start(p, North, 1, &[Left, Left, Left]),
+            start(p, North, 2, &[Left, Left]),
+            start(p, North, 3, &[Left]),
+            start(p, North, 4, &[]),
+            start(p, North, 5, &[Right]),
+            start(p, North, 6, &[Right, Right]),
+            start(p, North, 7, &[Right, Right, Right]),
+            start(p, North, 8, &[Right, Right, Right, Right]),
+            start(p, West, 1, &[Left, Ccw, Left, Left]),
+            start(p, West, 2, &[Left, Ccw, Left]),
+            start(p, West, 3, &[Left, Ccw]),
+            start(p, West, 4, &[Ccw]),
+            start(p, East, 4, &[Cw]),
+            start(p, East, 5, &[Right, Cw]),
+            start(p, East, 6, &[Right, Cw, Right]),
+            start(p, East, 7, &[Right, Cw, Right, Right]),
+            start(p, East, 8, &[Right, Cw, Right, Right, Right]),
+            start(p, East, 0, &[Left, Cw, Left, Left, Left]),
+            start(p, East, 1, &[Left, Cw, Left, Left]),
+            start(p, East, 2, &[Left, Cw, Left]),
+            start(p, East, 3, &[Left, Cw]),
+            start(p, West, 5, &[Right, Ccw]),
+            start(p, West, 6, &[Right, Ccw, Right]),
+            start(p, West, 7, &[Right, Ccw, Right, Right]),
+            start(p, West, 8, &[Right, Ccw, Right, Right, Right]),
+            start(p, West, 9, &[Right, Ccw, Right, Right, Right, Right]),
+            start(p, South, 1, &[Left, Cw, Left, Cw, Left]),
+            start(p, South, 2, &[Cw, Left, Cw, Left]),
+            start(p, South, 3, &[Cw, Left, Cw]),
+            start(p, South, 4, &[Cw, Cw]),
+            start(p, South, 5, &[Cw, Right, Cw]),
+            start(p, South, 6, &[Cw, Right, Cw, Right]),
+            start(p, South, 7, &[Right, Cw, Right, Cw, Right]),
+            start(p, South, 8, &[Right, Cw, Right, Cw, Right, Right]),
         ],
     }
 }
 
-fn start(
-    p: Piece,
-    r: RotationState,
-    x: i32,
-    i: &[PieceMovement],
-    time: u32,
-) -> (FallingPiece, InputList) {
+fn start(p: Piece, r: RotationState, x: i32, i: &[PieceMovement]) -> (FallingPiece, InputList) {
     (
         FallingPiece {
             kind: PieceState(p, r),
@@ -389,7 +570,7 @@ fn start(
         },
         InputList {
             movements: i.iter().copied().collect(),
-            time,
+            time: 0,
         },
     )
 }
@@ -0,0 +1,442 @@
+//! Perft-style checks of move generation. As with chess engines, counting the placement sequences
+//! reachable from a position to some depth and comparing against a simple reference
+//! implementation catches placements that `find_moves` misses or makes up.
+
+use std::collections::{HashSet, VecDeque};
+use std::convert::Infallible;
+use std::fmt;
+
+use crate::{
+    find_moves, Board, FallingPiece, FlipKicks, MovementCosts, MovementMode, Piece, PieceMovement,
+    RotationSystem, SpawnRule, TspinStatus,
+};
+
+/// The game rules move generation is checked under.
+#[derive(Copy, Clone, Debug)]
+pub struct Perft {
+    pub mode: MovementMode,
+    pub spawn_rule: SpawnRule,
+    pub rotation_system: RotationSystem,
+    pub flip_kicks: Option<FlipKicks>,
+}
+
+impl Default for Perft {
+    fn default() -> Self {
+        Perft {
+            mode: MovementMode::ZeroGComplete,
+            spawn_rule: SpawnRule::Row19Or20,
+            rotation_system: RotationSystem::Srs,
+            flip_kicks: None,
+        }
+    }
+}
+
+/// A piece for which `find_moves` and the reference implementation disagree.
+#[derive(Clone, Debug)]
+pub struct PerftMismatch {
+    pub board: Board,
+    pub spawned: FallingPiece,
+    /// Placements found by the reference implementation but not by `find_moves`.
+    pub missing: Vec<FallingPiece>,
+    /// Placements found by `find_moves` but not by the reference implementation.
+    pub extra: Vec<FallingPiece>,
+}
+
+impl fmt::Display for PerftMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "placements of {:?} differ on", self.spawned)?;
+        write!(f, "{}", self.board)?;
+        for piece in &self.missing {
+            writeln!(f, "missing {:?}", piece)?;
+        }
+        for piece in &self.extra {
+            writeln!(f, "extra {:?}", piece)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for PerftMismatch {}
+
+impl Perft {
+    /// The canonical locations of the placements found by `find_moves`.
+    pub fn placements(&self, board: &Board, spawned: FallingPiece) -> HashSet<FallingPiece> {
+        find_moves(
+            board,
+            spawned,
+            self.mode,
+            self.rotation_system,
+            self.flip_kicks,
+            MovementCosts::default(),
+        )
+        .iter()
+        .map(|p| p.location.canonical())
+        .collect()
+    }
+
+    /// The canonical locations of the placements found by trying every input from every position
+    /// the piece can reach. This is much slower than `find_moves`, but simple enough to trust.
+    pub fn reference_placements(
+        &self,
+        board: &Board,
+        mut spawned: FallingPiece,
+    ) -> HashSet<FallingPiece> {
+        let mut inputs = vec![
+            PieceMovement::Left,
+            PieceMovement::Right,
+            PieceMovement::Cw,
+            PieceMovement::Ccw,
+        ];
+        if self.flip_kicks.is_some() {
+            inputs.push(PieceMovement::Flip);
+        }
+        if self.mode != MovementMode::HardDropOnly {
+            inputs.push(PieceMovement::SonicDrop);
+        }
+        if self.mode == MovementMode::TwentyG {
+            spawned.sonic_drop(board);
+        }
+
+        // Positions are paired with whether the piece has been soft dropped, since pieces can't
+        // rotate after being soft dropped in HardDropAndTuck.
+        let mut placements = HashSet::new();
+        let mut checked = HashSet::new();
+        let mut queue = VecDeque::new();
+        checked.insert((spawned, false));
+        queue.push_back((spawned, false));
+        while let Some((piece, dropped)) = queue.pop_front() {
+            let mut locked = piece;
+            locked.sonic_drop(board);
+            if !board
+                .top_out
+                .locked_out(locked.cells().iter().map(|&(_, y)| y))
+            {
+                placements.insert(locked.canonical());
+            }
+
+            for &input in &inputs {
+                let rotation = matches!(
+                    input,
+                    PieceMovement::Cw | PieceMovement::Ccw | PieceMovement::Flip
+                );
+                if dropped && rotation && self.mode == MovementMode::HardDropAndTuck {
+                    continue;
+                }
+                let mut next = piece;
+                if !input.apply(&mut next, board, self.rotation_system, self.flip_kicks) {
+                    continue;
+                }
+                if self.mode == MovementMode::TwentyG {
+                    next.sonic_drop(board);
+                }
+                let next = (next, dropped || input == PieceMovement::SonicDrop);
+                if checked.insert(next) {
+                    queue.push_back(next);
+                }
+            }
+        }
+        placements
+    }
+
+    /// Counts the sequences of placements of the pieces in `queue`, in order and without hold.
+    /// Placements that lock out end the game and aren't counted.
+    pub fn count(&self, board: &mut Board, queue: &[Piece]) -> u64 {
+        match self.walk::<Infallible>(board, queue, &mut |_, _, _| Ok(())) {
+            Ok(count) => count,
+            Err(never) => match never {},
+        }
+    }
+
+    /// Counts placement sequences like `count`, checking the placements `find_moves` finds for
+    /// every piece along the way against the reference implementation. `ZeroG` skips some stack
+    /// movement for speed, so in that mode only placements the reference doesn't find are errors.
+    pub fn check(&self, board: &mut Board, queue: &[Piece]) -> Result<u64, PerftMismatch> {
+        self.walk(board, queue, &mut |board, spawned, found| {
+            let reference = self.reference_placements(board, spawned);
+            let mut missing: Vec<_> = reference.difference(found).copied().collect();
+            let extra: Vec<_> = found.difference(&reference).copied().collect();
+            if self.mode == MovementMode::ZeroG {
+                missing.clear();
+            }
+            if missing.is_empty() && extra.is_empty() {
+                Ok(())
+            } else {
+                Err(PerftMismatch {
+                    board: board.clone(),
+                    spawned,
+                    missing,
+                    extra,
+                })
+            }
+        })
+    }
+
+    fn walk<E>(
+        &self,
+        board: &mut Board,
+        queue: &[Piece],
+        visit: &mut impl FnMut(&Board, FallingPiece, &HashSet<FallingPiece>) -> Result<(), E>,
+    ) -> Result<u64, E> {
+        let (&piece, rest) = match queue.split_first() {
+            Some(split) => split,
+            None => return Ok(1),
+        };
+        let spawned = match self.spawn_rule.spawn(piece, board, self.rotation_system) {
+            Some(spawned) => spawned,
+            None => return Ok(0),
+        };
+        let placements = self.placements(board, spawned);
+        visit(board, spawned, &placements)?;
+
+        let mut count = 0;
+        for &placement in &placements {
+            let (result, undo) = board.lock_piece_undoable(placement);
+            if !result.locked_out {
+                count += self.walk(board, rest, visit)?;
+            }
+            board.undo(undo);
+        }
+        Ok(count)
+    }
+}
+
+/// A board with a placement that takes a tricky kick to reach.
+#[derive(Copy, Clone, Debug)]
+pub struct PerftFixture {
+    pub name: &'static str,
+    /// The board in the text format.
+    pub board: &'static str,
+    pub piece: Piece,
+    /// The field after the placement locks, in the text format.
+    pub result: &'static str,
+    pub spin: TspinStatus,
+}
+
+impl PerftFixture {
+    pub fn board(&self) -> Board {
+        self.board.parse().unwrap()
+    }
+
+    /// Whether `find_moves` finds the placement.
+    pub fn found_by(&self, perft: &Perft) -> bool {
+        let board = self.board();
+        let result: Board = self.result.parse().unwrap();
+        let spawned = match perft
+            .spawn_rule
+            .spawn(self.piece, &board, perft.rotation_system)
+        {
+            Some(spawned) => spawned,
+            None => return false,
+        };
+        perft.placements(&board, spawned).into_iter().any(|p| {
+            let mut board = board.clone();
+            board.lock_piece(p);
+            p.tspin == self.spin
+                && (0..board.height()).all(|y| board.get_row(y) == result.get_row(y))
+        })
+    }
+}
+
+/// Placements that take tricky SRS kicks, for checking the 0G movement modes.
+pub const FIXTURES: &[PerftFixture] = &[
+    PerftFixture {
+        name: "tst",
+        board: "
+            ##........
+            #.........
+            #.########
+            #..#######
+            #.########
+        ",
+        piece: Piece::T,
+        result: "
+            ##........
+            #.........
+        ",
+        spin: TspinStatus::Full,
+    },
+    PerftFixture {
+        name: "triple after two ccw rotations",
+        board: "
+            ..#.......
+            ..........
+            ##.#######
+            #..#######
+            ##.#######
+        ",
+        piece: Piece::T,
+        result: "
+            ..#.......
+            ..........
+        ",
+        spin: TspinStatus::Full,
+    },
+    PerftFixture {
+        name: "triple after two cw rotations",
+        board: "
+            .......#..
+            ..........
+            #######.##
+            #######..#
+            #######.##
+        ",
+        piece: Piece::T,
+        result: "
+            .......#..
+            ..........
+        ",
+        spin: TspinStatus::Full,
+    },
+    PerftFixture {
+        name: "i spin single",
+        board: "
+            ####.#####
+            ####.#####
+            ####....##
+        ",
+        piece: Piece::I,
+        result: "
+            ####.#####
+            ####.#####
+        ",
+        spin: TspinStatus::None,
+    },
+    PerftFixture {
+        name: "i spin tetris",
+        board: "
+            #########.
+            ..........
+            ########.#
+            ########.#
+            ########.#
+            ########.#
+        ",
+        piece: Piece::I,
+        result: "
+            #########.
+            ..........
+        ",
+        spin: TspinStatus::None,
+    },
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::CellColor;
+    use rand::prelude::*;
+    use rand::rngs::StdRng;
+
+    const MODES: [MovementMode; 5] = [
+        MovementMode::ZeroG,
+        MovementMode::ZeroGComplete,
+        MovementMode::TwentyG,
+        MovementMode::HardDropOnly,
+        MovementMode::HardDropAndTuck,
+    ];
+    const QUEUE: [Piece; 7] = [
+        Piece::T,
+        Piece::I,
+        Piece::O,
+        Piece::L,
+        Piece::J,
+        Piece::S,
+        Piece::Z,
+    ];
+
+    fn random_board(rng: &mut impl Rng, width: usize, height: usize) -> Board {
+        let mut board = Board::with_size(width, height);
+        let stack = rng.gen_range(0, height.min(18));
+        for y in 0..stack {
+            let density = rng.gen_range(0.2, 0.95);
+            let hole = rng.gen_range(0, width);
+            for x in 0..width {
+                if x != hole && rng.gen_bool(density) {
+                    board.set_cell_color(x as i32, y as i32, CellColor::Garbage);
+                }
+            }
+        }
+        board
+    }
+
+    fn check(perft: &Perft, board: &mut Board, queue: &[Piece]) -> u64 {
+        match perft.check(board, queue) {
+            Ok(count) => count,
+            Err(mismatch) => panic!("{:?} {:?}: {}", perft.mode, perft.flip_kicks, mismatch),
+        }
+    }
+
+    #[test]
+    fn empty_board() {
+        for &mode in &MODES {
+            for &flip_kicks in &[None, Some(FlipKicks::Tetrio)] {
+                let perft = Perft {
+                    mode,
+                    flip_kicks,
+                    ..Perft::default()
+                };
+                for depth in 1..=2 {
+                    let count = check(&perft, &mut Board::new(), &QUEUE[..depth]);
+                    assert_eq!(count, perft.count(&mut Board::new(), &QUEUE[..depth]));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn fixtures() {
+        for fixture in FIXTURES {
+            for &mode in &MODES {
+                let perft = Perft {
+                    mode,
+                    ..Perft::default()
+                };
+                check(&perft, &mut fixture.board(), &[fixture.piece, Piece::T]);
+                let zero_g = mode == MovementMode::ZeroG || mode == MovementMode::ZeroGComplete;
+                assert!(
+                    fixture.found_by(&perft) || !zero_g,
+                    "{:?} misses {}",
+                    mode,
+                    fixture.name
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn random_boards() {
+        let mut rng = StdRng::seed_from_u64(0);
+        for &(width, height) in &[(10, 40), (10, 22), (6, 12)] {
+            for i in 0..100 {
+                let board = random_board(&mut rng, width, height);
+                for &mode in &MODES {
+                    let perft = Perft {
+                        mode,
+                        flip_kicks: if i % 2 == 0 {
+                            Some(FlipKicks::Tetrio)
+                        } else {
+                            None
+                        },
+                        ..Perft::default()
+                    };
+                    let piece = QUEUE[i % 7];
+                    if let Some(spawned) =
+                        perft.spawn_rule.spawn(piece, &board, perft.rotation_system)
+                    {
+                        assert_eq!(
+                            crate::count_placements(
+                                &board,
+                                spawned,
+                                mode,
+                                perft.rotation_system,
+                                perft.flip_kicks
+                            ),
+                            perft.placements(&board, spawned).len()
+                        );
+                    }
+                    check(&perft, &mut board.clone(), &[piece]);
+                }
+            }
+        }
+    }
+}
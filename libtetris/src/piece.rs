@@ -2,7 +2,7 @@ use enum_map::Enum;
 use enumset::{enum_set, EnumSet, EnumSetType};
 use serde::{Deserialize, Serialize};
 
-use crate::{Board, Row};
+use crate::{BlockOut, Board, FlipKicks, RotationSystem, Row};
 
 #[derive(Copy, Clone, Debug, Hash, Eq, PartialEq, Serialize, Deserialize)]
 pub struct FallingPiece {
@@ -74,17 +74,21 @@ impl FallingPiece {
         }
     }
 
-    fn rotate<R: Row>(&mut self, target: PieceState, board: &Board<R>) -> bool {
+    fn rotate<R: Row>(
+        &mut self,
+        target: PieceState,
+        board: &Board<R>,
+        kicks: &[(i32, i32)],
+        rotation_system: RotationSystem,
+    ) -> bool {
         let initial = *self;
         self.kind = target;
-        let initial_offsets = initial.kind.rotation_points();
-        let target_offsets = target.rotation_points();
-        let kicks = initial_offsets
-            .iter()
-            .zip(target_offsets.iter())
-            .map(|(&(x1, y1), &(x2, y2))| (x1 - x2, y1 - y2));
+        // The TST kick upgrade only applies to quarter turns.
+        let mut flipped = initial.kind;
+        flipped.flip();
+        let quarter_turn = flipped != target;
 
-        for (i, (dx, dy)) in kicks.enumerate() {
+        for (i, &(dx, dy)) in kicks.iter().enumerate() {
             self.x = initial.x + dx;
             self.y = initial.y + dy;
             if !board.obstructed(self) {
@@ -104,7 +108,7 @@ impl FallingPiece {
                     }
 
                     if non_mini_corners + mini_corners >= 3 {
-                        if i == 4 || mini_corners == 2 {
+                        if i == 4 && quarter_turn || mini_corners == 2 {
                             self.tspin = TspinStatus::Full;
                         } else {
                             self.tspin = TspinStatus::Mini;
@@ -112,25 +116,72 @@ impl FallingPiece {
                     } else {
                         self.tspin = TspinStatus::None;
                     }
+                } else if target.0 != Piece::O {
+                    self.tspin = match board.spin_rule {
+                        SpinRule::TSpinsOnly => TspinStatus::None,
+                        _ if !self.immobile(board) => TspinStatus::None,
+                        SpinRule::AllMini => TspinStatus::Mini,
+                        SpinRule::AllSpin => TspinStatus::Full,
+                    };
                 }
                 return true;
             }
+            if i == 0 && rotation_system.center_column_rule(target.0) {
+                // ARS center column rule: T, L and J may not kick if the first blocked cell
+                // (reading top to bottom, left to right) is in the middle column.
+                let first_blocked = self
+                    .cells()
+                    .iter()
+                    .filter(|&&(x, y)| board.occupied(x, y))
+                    .min_by_key(|&&(x, y)| (-y, x))
+                    .copied();
+                if let Some((x, _)) = first_blocked {
+                    if x == self.x {
+                        break;
+                    }
+                }
+            }
         }
 
         *self = initial;
         false
     }
 
-    pub fn cw<R: Row>(&mut self, board: &Board<R>) -> bool {
+    pub fn cw<R: Row>(&mut self, board: &Board<R>, rotation_system: RotationSystem) -> bool {
         let mut target = self.kind;
         target.cw();
-        self.rotate(target, board)
+        let kicks = rotation_system.kicks(self.kind, target);
+        self.rotate(target, board, &kicks, rotation_system)
     }
 
-    pub fn ccw<R: Row>(&mut self, board: &Board<R>) -> bool {
+    pub fn ccw<R: Row>(&mut self, board: &Board<R>, rotation_system: RotationSystem) -> bool {
         let mut target = self.kind;
         target.ccw();
-        self.rotate(target, board)
+        let kicks = rotation_system.kicks(self.kind, target);
+        self.rotate(target, board, &kicks, rotation_system)
+    }
+
+    pub fn flip<R: Row>(
+        &mut self,
+        board: &Board<R>,
+        rotation_system: RotationSystem,
+        flip_kicks: FlipKicks,
+    ) -> bool {
+        let mut target = self.kind;
+        target.flip();
+        let kicks = rotation_system.flip_kicks(flip_kicks, self.kind, target);
+        self.rotate(target, board, &kicks, rotation_system)
+    }
+
+    /// Whether the piece is unable to move left, right, or up.
+    fn immobile<R: Row>(&self, board: &Board<R>) -> bool {
+        [(-1, 0), (1, 0), (0, 1)].iter().all(|&(dx, dy)| {
+            board.obstructed(&FallingPiece {
+                x: self.x + dx,
+                y: self.y + dy,
+                ..*self
+            })
+        })
     }
 
     pub fn same_location(&self, other: &Self) -> bool {
@@ -198,6 +249,89 @@ impl FallingPiece {
     }
 }
 
+macro_rules! gen_cells {
+    ($([$(($x:expr, $y:expr)),*]),*) => {
+        [$(
+            [$(($x, $y)),*],   // North
+            [$((-$x, -$y)),*], // South
+            [$(($y, -$x)),*],  // East
+            [$((-$y, $x)),*]   // West
+        ),*]
+    };
+}
+
+const CELLS: [[(i32, i32); 4]; 28] = gen_cells![
+    [(-1, 0), (0, 0), (1, 0), (2, 0)],  // I
+    [(0, 0), (1, 0), (0, 1), (1, 1)],   // O
+    [(-1, 0), (0, 0), (1, 0), (0, 1)],  // T
+    [(-1, 0), (0, 0), (1, 0), (1, 1)],  // L
+    [(-1, 0), (0, 0), (1, 0), (-1, 1)], // J
+    [(-1, 0), (0, 0), (0, 1), (1, 1)],  // S
+    [(-1, 1), (0, 1), (0, 0), (1, 0)]   // Z
+];
+
+/// The cells of a piece state as row bitmasks, so collisions can be checked a row at a time.
+#[derive(Copy, Clone, Debug)]
+pub(crate) struct PieceMask {
+    /// The leftmost and rightmost cell columns relative to the rotation point.
+    pub left: i32,
+    pub right: i32,
+    /// The lowest cell row relative to the rotation point.
+    pub bottom: i32,
+    /// The number of rows the piece spans.
+    pub height: usize,
+    /// Starting at `bottom`, the cells of each row with bit 0 at column `left`.
+    pub rows: [u16; 4],
+}
+
+const MASKS: [PieceMask; 28] = {
+    let mut masks = [PieceMask {
+        left: 0,
+        right: 0,
+        bottom: 0,
+        height: 0,
+        rows: [0; 4],
+    }; 28];
+    let mut i = 0;
+    while i < 28 {
+        let cells = CELLS[i];
+        let mut mask = PieceMask {
+            left: cells[0].0,
+            right: cells[0].0,
+            bottom: cells[0].1,
+            height: 0,
+            rows: [0; 4],
+        };
+        let mut j = 1;
+        while j < 4 {
+            let (x, y) = cells[j];
+            if x < mask.left {
+                mask.left = x;
+            }
+            if x > mask.right {
+                mask.right = x;
+            }
+            if y < mask.bottom {
+                mask.bottom = y;
+            }
+            j += 1;
+        }
+        j = 0;
+        while j < 4 {
+            let (x, y) = cells[j];
+            let row = (y - mask.bottom) as usize;
+            mask.rows[row] |= 1 << (x - mask.left);
+            if row >= mask.height {
+                mask.height = row + 1;
+            }
+            j += 1;
+        }
+        masks[i] = mask;
+        i += 1;
+    }
+    masks
+};
+
 #[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
 pub enum CellColor {
     I,
@@ -234,6 +368,8 @@ pub enum RotationState {
 #[derive(Copy, Clone, Debug, Hash, Eq, PartialEq, Serialize, Deserialize)]
 pub struct PieceState(pub Piece, pub RotationState);
 
+/// Spin status of a piece. Despite the name, this applies to all pieces when the board's
+/// `SpinRule` awards non-T spins.
 #[derive(Copy, Clone, Debug, Hash, Eq, PartialEq, Serialize, Deserialize)]
 pub enum TspinStatus {
     None,
@@ -241,6 +377,25 @@ pub enum TspinStatus {
     Full,
 }
 
+/// Which pieces can spin, and how spins are detected.
+#[derive(Copy, Clone, Debug, Hash, Eq, PartialEq, Serialize, Deserialize)]
+pub enum SpinRule {
+    /// Only T pieces spin, detected with the 3-corner rule.
+    TSpinsOnly,
+    /// T-spins use the 3-corner rule; other pieces get a mini spin if they can't move after
+    /// rotating (TETR.IO's all-mini).
+    AllMini,
+    /// T-spins use the 3-corner rule; other pieces get a full spin if they can't move after
+    /// rotating.
+    AllSpin,
+}
+
+impl Default for SpinRule {
+    fn default() -> Self {
+        SpinRule::TSpinsOnly
+    }
+}
+
 impl RotationState {
     pub fn cw(&mut self) {
         use RotationState::*;
@@ -262,6 +417,16 @@ impl RotationState {
         }
     }
 
+    pub fn flip(&mut self) {
+        use RotationState::*;
+        match self {
+            North => *self = South,
+            East => *self = West,
+            South => *self = North,
+            West => *self = East,
+        }
+    }
+
     pub fn mini_tspin_corners(self) -> [(i32, i32); 2] {
         use RotationState::*;
         match self {
@@ -292,31 +457,21 @@ impl PieceState {
         self.1.ccw()
     }
 
+    pub fn flip(&mut self) {
+        self.1.flip()
+    }
+
     /// Returns the cells this piece and orientation occupy relative to rotation point 1, as well
     /// as the connection directions, in no particular order.
     #[inline(always)]
     pub fn cells(&self) -> [(i32, i32); 4] {
-        macro_rules! gen_cells {
-            ($([$(($x:expr, $y:expr)),*]),*) => {
-                [$(
-                    [$(($x, $y)),*],   // North
-                    [$((-$x, -$y)),*], // South
-                    [$(($y, -$x)),*],  // East
-                    [$((-$y, $x)),*]   // West
-                ),*]
-            };
-        }
-        const CELLS: &'static [[(i32, i32); 4]] = &gen_cells![
-            [(-1, 0), (0, 0), (1, 0), (2, 0)],  // I
-            [(0, 0), (1, 0), (0, 1), (1, 1)],   // O
-            [(-1, 0), (0, 0), (1, 0), (0, 1)],  // T
-            [(-1, 0), (0, 0), (1, 0), (1, 1)],  // L
-            [(-1, 0), (0, 0), (1, 0), (-1, 1)], // J
-            [(-1, 0), (0, 0), (0, 1), (1, 1)],  // S
-            [(-1, 1), (0, 1), (0, 0), (1, 0)]   // Z
-        ];
-        let index = self.0 as usize * 4 + self.1 as usize;
-        CELLS[index]
+        CELLS[self.0 as usize * 4 + self.1 as usize]
+    }
+
+    /// Returns the cells this piece and orientation occupy as a bitmask for each row.
+    #[inline(always)]
+    pub(crate) fn mask(&self) -> &'static PieceMask {
+        &MASKS[self.0 as usize * 4 + self.1 as usize]
     }
 
     pub fn cells_with_connections(&self) -> [(i32, i32, EnumSet<Direction>); 4] {
@@ -439,6 +594,20 @@ impl Piece {
         }
     }
 
+    /// The inverse of `to_char`. Lowercase letters are accepted.
+    pub fn from_char(c: char) -> Option<Piece> {
+        match c.to_ascii_uppercase() {
+            'I' => Some(Piece::I),
+            'T' => Some(Piece::T),
+            'O' => Some(Piece::O),
+            'L' => Some(Piece::L),
+            'J' => Some(Piece::J),
+            'S' => Some(Piece::S),
+            'Z' => Some(Piece::Z),
+            _ => None,
+        }
+    }
+
     pub fn color(self) -> CellColor {
         match self {
             Piece::I => CellColor::I,
@@ -459,15 +628,28 @@ pub enum PieceMovement {
     Cw,
     Ccw,
     SonicDrop,
+    /// 180 degree rotation. Declared last so that existing serialized movements keep their
+    /// variant indices.
+    Flip,
 }
 
 impl PieceMovement {
-    pub fn apply(self, piece: &mut FallingPiece, board: &Board) -> bool {
+    pub fn apply(
+        self,
+        piece: &mut FallingPiece,
+        board: &Board,
+        rotation_system: RotationSystem,
+        flip_kicks: Option<FlipKicks>,
+    ) -> bool {
         match self {
             PieceMovement::Left => piece.shift(board, -1, 0),
             PieceMovement::Right => piece.shift(board, 1, 0),
-            PieceMovement::Ccw => piece.ccw(board),
-            PieceMovement::Cw => piece.cw(board),
+            PieceMovement::Ccw => piece.ccw(board, rotation_system),
+            PieceMovement::Cw => piece.cw(board, rotation_system),
+            PieceMovement::Flip => match flip_kicks {
+                Some(flip_kicks) => piece.flip(board, rotation_system, flip_kicks),
+                None => false,
+            },
             PieceMovement::SonicDrop => piece.sonic_drop(board),
         }
     }
@@ -517,13 +699,27 @@ pub enum SpawnRule {
 }
 
 impl SpawnRule {
-    pub fn spawn<R: Row>(self, piece: Piece, board: &Board<R>) -> Option<FallingPiece> {
+    pub fn spawn<R: Row>(
+        self,
+        piece: Piece,
+        board: &Board<R>,
+        rotation_system: RotationSystem,
+    ) -> Option<FallingPiece> {
+        let kind = PieceState(piece, rotation_system.spawn_orientation(piece));
+        // Spawn rows refer to the lowest cell of the piece, which is not the rotation point for
+        // pieces spawning upside down.
+        let lowest = kind.cells().iter().map(|&(_, y)| y).min().unwrap();
+        let highest = kind.cells().iter().map(|&(_, y)| y).max().unwrap();
+        // Pieces spawn in the middle of the board, and low enough to fit under the ceiling of
+        // boards shorter than 22 rows.
+        let x = (board.width() - 1) / 2;
+        let row = |row: i32| row.min(board.height() - 1 - (highest - lowest)) - lowest;
         match self {
             SpawnRule::Row19Or20 => {
                 let mut spawned = FallingPiece {
-                    kind: PieceState(piece, RotationState::North),
-                    x: 4,
-                    y: 19,
+                    kind,
+                    x,
+                    y: row(19),
                     tspin: TspinStatus::None,
                 };
                 if !board.obstructed(&spawned) {
@@ -536,9 +732,9 @@ impl SpawnRule {
             }
             SpawnRule::Row21AndFall => {
                 let mut spawned = FallingPiece {
-                    kind: PieceState(piece, RotationState::North),
-                    x: 4,
-                    y: 21,
+                    kind,
+                    x,
+                    y: row(21),
                     tspin: TspinStatus::None,
                 };
                 if !board.obstructed(&spawned) {
@@ -547,6 +743,21 @@ impl SpawnRule {
                 }
             }
         }
+        if board.top_out.block_out == BlockOut::Raise {
+            let mut spawned = FallingPiece {
+                kind,
+                x,
+                y: row(19),
+                tspin: TspinStatus::None,
+            };
+            // Cells above the board are obstructed, so this stops at the top of the board.
+            while spawned.cells().iter().all(|&(_, y)| y < board.height()) {
+                if !board.obstructed(&spawned) {
+                    return Some(spawned);
+                }
+                spawned.y += 1;
+            }
+        }
         None
     }
 }
@@ -0,0 +1,214 @@
+//! Placement enumeration for the movement modes that don't need `find_moves`'s general search.
+//! Without 0G stack movement the positions a piece can reach are few enough that they're tracked
+//! in bitmasks instead of hashsets.
+
+use std::collections::{BinaryHeap, HashMap};
+
+use arrayvec::ArrayVec;
+
+use crate::moves::{lock_check, precomputed_starts_apply, zero_g_starts};
+use crate::{
+    Board, FallingPiece, FlipKicks, InputList, MovementCosts, Piece, PieceMovement, Placement,
+    RotationSystem, MAX_HEIGHT,
+};
+
+const ROWS: usize = MAX_HEIGHT + 4;
+
+/// A set of piece positions, stored as a bitmask of columns for each spin status, rotation state
+/// and row.
+struct PositionSet {
+    rows: [u32; 3 * 4 * ROWS],
+}
+
+impl PositionSet {
+    fn new() -> Self {
+        PositionSet {
+            rows: [0; 3 * 4 * ROWS],
+        }
+    }
+
+    /// Adds the position, returning whether it wasn't already in the set.
+    fn insert(&mut self, piece: FallingPiece) -> bool {
+        // The rotation point of a piece that fits on the board is at most two cells outside it.
+        let row =
+            (piece.tspin as usize * 4 + piece.kind.1 as usize) * ROWS + (piece.y + 2) as usize;
+        let bit = 1 << (piece.x + 2);
+        let new = self.rows[row] & bit == 0;
+        self.rows[row] |= bit;
+        new
+    }
+}
+
+/// The inputs that move a piece without dropping it, in the order the general search tries them.
+fn inputs(piece: Piece, flip_kicks: Option<FlipKicks>) -> ArrayVec<[PieceMovement; 5]> {
+    let mut inputs = ArrayVec::new();
+    inputs.push(PieceMovement::Left);
+    inputs.push(PieceMovement::Right);
+    if piece != Piece::O {
+        inputs.push(PieceMovement::Cw);
+        inputs.push(PieceMovement::Ccw);
+        if flip_kicks.is_some() {
+            inputs.push(PieceMovement::Flip);
+        }
+    }
+    inputs
+}
+
+/// Finds the placements reachable by moving the piece without dropping it and then hard dropping
+/// it. With `tucks`, also finds the placements reachable by soft dropping it instead and then
+/// shifting it along the stack.
+pub(crate) fn hard_drop(
+    board: &Board,
+    spawned: FallingPiece,
+    rotation_system: RotationSystem,
+    flip_kicks: Option<FlipKicks>,
+    costs: MovementCosts,
+    tucks: bool,
+) -> Vec<Placement> {
+    let mut locks = HashMap::with_capacity(64);
+    let mut dropped_queue = BinaryHeap::new();
+    let mut drop = |place: FallingPiece, mut inputs: InputList| {
+        let orig_y = place.y;
+        let mut dropped = place;
+        dropped.sonic_drop(board);
+        lock_check(board, dropped, &mut locks, inputs.clone());
+        if tucks && !inputs.movements.is_full() {
+            inputs.movements.push(PieceMovement::SonicDrop);
+            inputs.time += costs.soft_drop(orig_y - dropped.y);
+            dropped_queue.push(Placement {
+                inputs,
+                location: dropped,
+            });
+        }
+    };
+
+    if precomputed_starts_apply(board, rotation_system) {
+        for (place, mut inputs) in zero_g_starts(spawned.kind.0, flip_kicks.is_some()) {
+            inputs.time =
+                costs.start_time(board, place, &inputs.movements, rotation_system, flip_kicks);
+            drop(place, inputs);
+        }
+    } else {
+        let mut checked = PositionSet::new();
+        let mut check_queue = BinaryHeap::new();
+        checked.insert(spawned);
+        check_queue.push(Placement {
+            inputs: InputList {
+                movements: ArrayVec::new(),
+                time: 0,
+            },
+            location: spawned,
+        });
+        while let Some(Placement { inputs, location }) = check_queue.pop() {
+            if !inputs.movements.is_full() {
+                for &input in &self::inputs(spawned.kind.0, flip_kicks) {
+                    let mut piece = location;
+                    if input.apply(&mut piece, board, rotation_system, flip_kicks)
+                        && checked.insert(piece)
+                    {
+                        let mut inputs = inputs.clone();
+                        inputs.time += MovementCosts::tap(input, inputs.movements.last().copied());
+                        inputs.movements.push(input);
+                        check_queue.push(Placement {
+                            inputs,
+                            location: piece,
+                        });
+                    }
+                }
+            }
+            drop(location, inputs);
+        }
+    }
+
+    // Tucks. Positions are marked as checked when they're taken off the queue since several
+    // hard drop paths can drop the piece to the same position.
+    let mut checked = PositionSet::new();
+    while let Some(Placement { inputs, location }) = dropped_queue.pop() {
+        if !checked.insert(location) {
+            continue;
+        }
+        let mut dropped = location;
+        dropped.sonic_drop(board);
+        lock_check(board, dropped, &mut locks, inputs.clone());
+        if inputs.movements.is_full() {
+            continue;
+        }
+        if dropped != location {
+            // Shifting off the stack leaves the piece floating until it's soft dropped again.
+            let mut inputs = inputs.clone();
+            inputs.movements.push(PieceMovement::SonicDrop);
+            inputs.time += costs.soft_drop(location.y - dropped.y);
+            dropped_queue.push(Placement {
+                inputs,
+                location: dropped,
+            });
+        }
+        for &input in &[PieceMovement::Left, PieceMovement::Right] {
+            let mut piece = location;
+            if input.apply(&mut piece, board, rotation_system, flip_kicks) {
+                let mut inputs = inputs.clone();
+                inputs.time += MovementCosts::tap(input, inputs.movements.last().copied());
+                inputs.movements.push(input);
+                dropped_queue.push(Placement {
+                    inputs,
+                    location: piece,
+                });
+            }
+        }
+    }
+
+    locks.into_values().collect()
+}
+
+/// Finds the placements reachable when the piece falls to the stack instantly after every input.
+pub(crate) fn twenty_g(
+    board: &Board,
+    mut spawned: FallingPiece,
+    rotation_system: RotationSystem,
+    flip_kicks: Option<FlipKicks>,
+) -> Vec<Placement> {
+    let mut locks = HashMap::with_capacity(64);
+    let mut checked = PositionSet::new();
+    let mut check_queue = BinaryHeap::new();
+
+    spawned.sonic_drop(board);
+    let mut movements = ArrayVec::new();
+    movements.push(PieceMovement::SonicDrop);
+    checked.insert(spawned);
+    check_queue.push(Placement {
+        inputs: InputList { movements, time: 0 },
+        location: spawned,
+    });
+
+    while let Some(Placement { inputs, location }) = check_queue.pop() {
+        if !inputs.movements.is_full() {
+            for &input in &self::inputs(spawned.kind.0, flip_kicks) {
+                let mut piece = location;
+                if input.apply(&mut piece, board, rotation_system, flip_kicks) {
+                    let mut inputs = inputs.clone();
+                    inputs.time += MovementCosts::tap(input, inputs.movements.last().copied());
+                    inputs.movements.push(input);
+                    // 20G causes instant plummet, but we might actually be playing a high gravity
+                    // mode that we're approximating as 20G so we need to add a sonic drop movement
+                    // to signal to the input engine that we need the piece to hit the ground
+                    // before continuing.
+                    let dropped = piece.sonic_drop(board);
+                    if checked.insert(piece) {
+                        if dropped && !inputs.movements.is_full() {
+                            // If the move list is full this has to be the last move and the input
+                            // engine should hard drop.
+                            inputs.movements.push(PieceMovement::SonicDrop);
+                        }
+                        check_queue.push(Placement {
+                            inputs,
+                            location: piece,
+                        });
+                    }
+                }
+            }
+        }
+        lock_check(board, location, &mut locks, inputs);
+    }
+
+    locks.into_values().collect()
+}
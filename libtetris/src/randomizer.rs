@@ -0,0 +1,469 @@
+use enum_map::EnumMap;
+use enumset::EnumSet;
+use rand::distributions::WeightedIndex;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::Piece;
+
+/// Tracks which pieces can be dealt next as the queue is revealed.
+pub trait Randomizer {
+    /// The relative likelihood of each piece being dealt next. Pieces that can't be dealt next
+    /// have a weight of 0.
+    fn weights(&self) -> EnumMap<Piece, u32>;
+
+    /// Updates the state after `piece` is dealt.
+    fn deal(&mut self, piece: Piece);
+
+    /// Reverses `deal`, returning to the state before `piece` was dealt. When the state doesn't
+    /// say how `piece` was dealt, this picks a state it could have been dealt from.
+    fn undeal(&mut self, piece: Piece);
+
+    /// The set of pieces that can be dealt next.
+    fn possible(&self) -> EnumSet<Piece> {
+        self.weights()
+            .iter()
+            .filter(|&(_, &w)| w != 0)
+            .map(|(p, _)| p)
+            .collect()
+    }
+
+    /// Randomly selects the next piece.
+    ///
+    /// This does not update the state. Use `deal()` once the piece is added to the queue.
+    fn generate(&self, rng: &mut impl Rng) -> Piece {
+        let weights = self.weights();
+        let index = rng.sample(WeightedIndex::new(weights.values()).unwrap());
+        let (piece, _) = weights.iter().nth(index).unwrap();
+        piece
+    }
+}
+
+/// The randomizer state stored on a `Board`.
+#[derive(Copy, Clone, Debug, Hash, Eq, PartialEq, Serialize, Deserialize)]
+pub enum PieceRandomizer {
+    SevenBag(SevenBag),
+    FourteenBag(FourteenBag),
+    Random(PureRandom),
+    History(History),
+    BagWithExtra(BagWithExtra),
+}
+
+/// Deals each of the 7 pieces once in a random order, then repeats.
+#[derive(Copy, Clone, Debug, Hash, Eq, PartialEq, Serialize, Deserialize)]
+pub struct SevenBag {
+    /// The pieces remaining in the current bag.
+    pub bag: EnumSet<Piece>,
+}
+
+/// Deals each of the 7 pieces twice in a random order, then repeats.
+#[derive(Copy, Clone, Debug, Hash, Eq, PartialEq, Serialize, Deserialize)]
+pub struct FourteenBag {
+    /// Pieces with at least one copy remaining in the current bag.
+    pub once: EnumSet<Piece>,
+    /// Pieces with both copies remaining in the current bag.
+    pub twice: EnumSet<Piece>,
+}
+
+/// Every piece is equally likely to be dealt.
+#[derive(Copy, Clone, Debug, Default, Hash, Eq, PartialEq, Serialize, Deserialize)]
+pub struct PureRandom;
+
+/// TGM style history randomizer. A piece is rolled up to `rolls` times, rerolling if it is in the
+/// history of the last 4 pieces dealt; the last roll is dealt regardless.
+#[derive(Copy, Clone, Debug, Hash, Eq, PartialEq, Serialize, Deserialize)]
+pub struct History {
+    pub history: [Piece; 4],
+    /// At least 1. More than `History::MAX_ROLLS` rolls are treated as `MAX_ROLLS`.
+    pub rolls: u32,
+    /// The first piece is never S, Z or O.
+    pub first: bool,
+}
+
+/// A 7-bag with one extra random piece shuffled into each bag.
+#[derive(Copy, Clone, Debug, Hash, Eq, PartialEq, Serialize, Deserialize)]
+pub struct BagWithExtra {
+    /// The pieces remaining in the current bag, not counting the extra piece.
+    pub bag: EnumSet<Piece>,
+    /// Whether the extra piece of the current bag has yet to be dealt.
+    pub extra: bool,
+}
+
+impl PieceRandomizer {
+    /// Sets the pieces remaining in the current bag, for starting from the middle of a game. An
+    /// empty set means a new bag.
+    ///
+    /// A 14-bag is given one copy of each remaining piece, and a bag with an extra piece is given
+    /// its extra piece. Randomizers without a bag are unchanged.
+    pub fn set_bag(&mut self, remaining: EnumSet<Piece>) {
+        if remaining.is_empty() {
+            return;
+        }
+        match self {
+            PieceRandomizer::SevenBag(r) => r.bag = remaining,
+            PieceRandomizer::FourteenBag(r) => {
+                r.once = remaining;
+                r.twice = EnumSet::empty();
+            }
+            PieceRandomizer::BagWithExtra(r) => {
+                r.bag = remaining;
+                r.extra = true;
+            }
+            PieceRandomizer::Random(_) | PieceRandomizer::History(_) => {}
+        }
+    }
+}
+
+impl SevenBag {
+    pub fn new() -> Self {
+        SevenBag {
+            bag: EnumSet::all(),
+        }
+    }
+}
+
+impl Default for SevenBag {
+    fn default() -> Self {
+        SevenBag::new()
+    }
+}
+
+impl FourteenBag {
+    pub fn new() -> Self {
+        FourteenBag {
+            once: EnumSet::all(),
+            twice: EnumSet::all(),
+        }
+    }
+}
+
+impl Default for FourteenBag {
+    fn default() -> Self {
+        FourteenBag::new()
+    }
+}
+
+impl History {
+    /// The most rolls whose weights fit in a `u32`. Each further roll changes the chance of any
+    /// piece by less than 0.1%.
+    pub const MAX_ROLLS: u32 = 11;
+
+    /// The original TGM randomizer: 4 rolls, with a history initially full of Z pieces.
+    pub fn tgm1() -> Self {
+        History {
+            history: [Piece::Z; 4],
+            rolls: 4,
+            first: true,
+        }
+    }
+
+    /// The TGM2 randomizer: 6 rolls, with a history initially of Z, S, S, Z.
+    pub fn tgm2() -> Self {
+        History {
+            history: [Piece::Z, Piece::S, Piece::S, Piece::Z],
+            rolls: 6,
+            first: true,
+        }
+    }
+}
+
+impl BagWithExtra {
+    pub fn new() -> Self {
+        BagWithExtra {
+            bag: EnumSet::all(),
+            extra: true,
+        }
+    }
+}
+
+impl Default for BagWithExtra {
+    fn default() -> Self {
+        BagWithExtra::new()
+    }
+}
+
+impl Randomizer for SevenBag {
+    fn weights(&self) -> EnumMap<Piece, u32> {
+        let mut weights = EnumMap::new();
+        for p in self.bag {
+            weights[p] = 1;
+        }
+        weights
+    }
+
+    fn deal(&mut self, piece: Piece) {
+        self.bag.remove(piece);
+        if self.bag.is_empty() {
+            self.bag = EnumSet::all();
+        }
+    }
+
+    fn undeal(&mut self, piece: Piece) {
+        if self.bag == EnumSet::all() {
+            self.bag = EnumSet::empty();
+        }
+        self.bag.insert(piece);
+    }
+
+    fn possible(&self) -> EnumSet<Piece> {
+        self.bag
+    }
+}
+
+impl Randomizer for FourteenBag {
+    fn weights(&self) -> EnumMap<Piece, u32> {
+        let mut weights = EnumMap::new();
+        for p in self.once {
+            weights[p] = 1 + self.twice.contains(p) as u32;
+        }
+        weights
+    }
+
+    fn deal(&mut self, piece: Piece) {
+        if !self.twice.remove(piece) {
+            self.once.remove(piece);
+        }
+        if self.once.is_empty() {
+            *self = FourteenBag::new();
+        }
+    }
+
+    fn undeal(&mut self, piece: Piece) {
+        if *self == FourteenBag::new() {
+            self.once = EnumSet::empty();
+            self.twice = EnumSet::empty();
+        }
+        if self.once.contains(piece) {
+            self.twice.insert(piece);
+        } else {
+            self.once.insert(piece);
+        }
+    }
+
+    fn possible(&self) -> EnumSet<Piece> {
+        self.once
+    }
+}
+
+impl Randomizer for PureRandom {
+    fn weights(&self) -> EnumMap<Piece, u32> {
+        let mut weights = EnumMap::new();
+        for (_, w) in weights.iter_mut() {
+            *w = 1;
+        }
+        weights
+    }
+
+    fn deal(&mut self, _: Piece) {}
+
+    fn undeal(&mut self, _: Piece) {}
+
+    fn possible(&self) -> EnumSet<Piece> {
+        EnumSet::all()
+    }
+}
+
+impl Randomizer for History {
+    fn weights(&self) -> EnumMap<Piece, u32> {
+        let mut weights = EnumMap::new();
+        if self.first {
+            for &p in &[Piece::I, Piece::T, Piece::L, Piece::J] {
+                weights[p] = 1;
+            }
+            return weights;
+        }
+        let history: EnumSet<Piece> = self.history.iter().copied().collect();
+        let k = history.len() as u32;
+        let n = self.rolls.max(1).min(History::MAX_ROLLS);
+        // Scaled by 7^n, so the weights sum to 7^n: a piece in the history is only dealt if every roll lands in the history,
+        // while a piece outside it is dealt if it comes up after any number of rerolls.
+        let in_history = k.pow(n - 1);
+        let not_in_history = (0..n).map(|i| k.pow(i) * 7u32.pow(n - 1 - i)).sum();
+        for (p, w) in weights.iter_mut() {
+            *w = if history.contains(p) {
+                in_history
+            } else {
+                not_in_history
+            };
+        }
+        weights
+    }
+
+    fn deal(&mut self, piece: Piece) {
+        self.history.rotate_left(1);
+        self.history[3] = piece;
+        self.first = false;
+    }
+
+    /// The piece that left the history when `piece` was dealt is lost, so this assumes it was
+    /// `piece` again. Whether `piece` was the first piece is lost too, so `first` is unchanged.
+    fn undeal(&mut self, piece: Piece) {
+        self.history.rotate_right(1);
+        self.history[0] = piece;
+    }
+}
+
+impl Randomizer for BagWithExtra {
+    fn weights(&self) -> EnumMap<Piece, u32> {
+        // The extra piece can be any piece, so each remaining bag piece is 7 times as likely to
+        // come from the bag as from the extra piece.
+        let mut weights = EnumMap::new();
+        for (p, w) in weights.iter_mut() {
+            *w = 7 * self.bag.contains(p) as u32 + self.extra as u32;
+        }
+        weights
+    }
+
+    fn deal(&mut self, piece: Piece) {
+        if !self.bag.remove(piece) {
+            self.extra = false;
+        }
+        if self.bag.is_empty() && !self.extra {
+            *self = BagWithExtra::new();
+        }
+    }
+
+    /// A piece that isn't left in the bag could have come from the bag or have been the extra
+    /// piece. This assumes it was the extra piece if that has been dealt, which keeps every piece
+    /// possible.
+    fn undeal(&mut self, piece: Piece) {
+        if *self == BagWithExtra::new() {
+            self.bag = EnumSet::empty();
+            self.extra = false;
+        }
+        if self.extra {
+            self.bag.insert(piece);
+        } else {
+            self.extra = true;
+        }
+    }
+}
+
+impl Randomizer for PieceRandomizer {
+    fn weights(&self) -> EnumMap<Piece, u32> {
+        match self {
+            PieceRandomizer::SevenBag(r) => r.weights(),
+            PieceRandomizer::FourteenBag(r) => r.weights(),
+            PieceRandomizer::Random(r) => r.weights(),
+            PieceRandomizer::History(r) => r.weights(),
+            PieceRandomizer::BagWithExtra(r) => r.weights(),
+        }
+    }
+
+    fn deal(&mut self, piece: Piece) {
+        match self {
+            PieceRandomizer::SevenBag(r) => r.deal(piece),
+            PieceRandomizer::FourteenBag(r) => r.deal(piece),
+            PieceRandomizer::Random(r) => r.deal(piece),
+            PieceRandomizer::History(r) => r.deal(piece),
+            PieceRandomizer::BagWithExtra(r) => r.deal(piece),
+        }
+    }
+
+    fn undeal(&mut self, piece: Piece) {
+        match self {
+            PieceRandomizer::SevenBag(r) => r.undeal(piece),
+            PieceRandomizer::FourteenBag(r) => r.undeal(piece),
+            PieceRandomizer::Random(r) => r.undeal(piece),
+            PieceRandomizer::History(r) => r.undeal(piece),
+            PieceRandomizer::BagWithExtra(r) => r.undeal(piece),
+        }
+    }
+
+    fn possible(&self) -> EnumSet<Piece> {
+        match self {
+            PieceRandomizer::SevenBag(r) => r.possible(),
+            PieceRandomizer::FourteenBag(r) => r.possible(),
+            PieceRandomizer::Random(r) => r.possible(),
+            PieceRandomizer::History(r) => r.possible(),
+            PieceRandomizer::BagWithExtra(r) => r.possible(),
+        }
+    }
+}
+
+impl Default for PieceRandomizer {
+    fn default() -> Self {
+        PieceRandomizer::SevenBag(SevenBag::new())
+    }
+}
+
+impl From<SevenBag> for PieceRandomizer {
+    fn from(r: SevenBag) -> Self {
+        PieceRandomizer::SevenBag(r)
+    }
+}
+
+impl From<FourteenBag> for PieceRandomizer {
+    fn from(r: FourteenBag) -> Self {
+        PieceRandomizer::FourteenBag(r)
+    }
+}
+
+impl From<PureRandom> for PieceRandomizer {
+    fn from(r: PureRandom) -> Self {
+        PieceRandomizer::Random(r)
+    }
+}
+
+impl From<History> for PieceRandomizer {
+    fn from(r: History) -> Self {
+        PieceRandomizer::History(r)
+    }
+}
+
+impl From<BagWithExtra> for PieceRandomizer {
+    fn from(r: BagWithExtra) -> Self {
+        PieceRandomizer::BagWithExtra(r)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::prelude::*;
+    use rand::rngs::StdRng;
+
+    /// Deals 50 pieces, then undeals them, checking each state against the one it was dealt from.
+    fn check_undeal(mut r: PieceRandomizer, check: impl Fn(&PieceRandomizer, &PieceRandomizer)) {
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut history = vec![];
+        for _ in 0..50 {
+            let piece = r.generate(&mut rng);
+            history.push((r, piece));
+            r.deal(piece);
+        }
+        while let Some((before, piece)) = history.pop() {
+            r.undeal(piece);
+            assert!(r.possible().contains(piece));
+            check(&r, &before);
+        }
+    }
+
+    #[test]
+    fn undeal_is_exact_for_bags() {
+        let exact = |r: &PieceRandomizer, before: &PieceRandomizer| assert_eq!(r, before);
+        check_undeal(SevenBag::new().into(), exact);
+        check_undeal(FourteenBag::new().into(), exact);
+        check_undeal(PureRandom.into(), exact);
+    }
+
+    #[test]
+    fn undeal_keeps_possible_pieces() {
+        let superset = |r: &PieceRandomizer, before: &PieceRandomizer| {
+            assert!(r.possible().is_superset(before.possible()))
+        };
+        check_undeal(BagWithExtra::new().into(), superset);
+        check_undeal(History::tgm2().into(), superset);
+    }
+
+    #[test]
+    fn history_weights_fit_with_many_rolls() {
+        let mut r = History::tgm2();
+        r.first = false;
+        for &rolls in &[1, History::MAX_ROLLS, 100, u32::MAX] {
+            r.rolls = rolls;
+            let total: u64 = r.weights().values().map(|&w| w as u64).sum();
+            assert_eq!(total, 7u64.pow(rolls.min(History::MAX_ROLLS)));
+        }
+    }
+}
@@ -0,0 +1,300 @@
+use arrayvec::ArrayVec;
+use serde::{Deserialize, Serialize};
+
+use crate::{Piece, PieceState, RotationState};
+
+/// The rotation system used to resolve kicks and spawn orientations.
+#[derive(Copy, Clone, Debug, Hash, Eq, PartialEq, Serialize, Deserialize)]
+pub enum RotationSystem {
+    /// Guideline SRS.
+    Srs,
+    /// SRS with the symmetric I kicks used by TETR.IO.
+    SrsPlus,
+    /// Pieces rotate in place about their SRS rotation point; rotation fails if obstructed.
+    NoKicks,
+    /// The Arika rotation system used in TGM.
+    Ars,
+}
+
+impl Default for RotationSystem {
+    fn default() -> Self {
+        RotationSystem::Srs
+    }
+}
+
+impl RotationSystem {
+    /// Returns the translations to test, in order, when rotating from `from` to `to`.
+    pub fn kicks(self, from: PieceState, to: PieceState) -> ArrayVec<[(i32, i32); 5]> {
+        match self {
+            RotationSystem::Srs => srs_kicks(from, to).collect(),
+            RotationSystem::NoKicks => srs_kicks(from, to).take(1).collect(),
+            RotationSystem::SrsPlus => {
+                if from.0 != Piece::I {
+                    return srs_kicks(from, to).collect();
+                }
+                let (bx, by) = srs_kicks(from, to).next().unwrap();
+                srs_plus_i_kicks(from.1, to.1)
+                    .iter()
+                    .map(|&(x, y)| (bx + x, by + y))
+                    .collect()
+            }
+            RotationSystem::Ars => {
+                let (fx, fy) = ars_offset(from);
+                let (tx, ty) = ars_offset(to);
+                let (bx, by) = (tx - fx, ty - fy);
+                let kicks: &[_] = match from.0 {
+                    Piece::I => &[(0, 0)],
+                    _ => &[(0, 0), (1, 0), (-1, 0)],
+                };
+                kicks.iter().map(|&(x, y)| (bx + x, by + y)).collect()
+            }
+        }
+    }
+
+    /// Returns the translations to test, in order, when rotating 180 degrees from `from` to `to`
+    /// using the given 180 kick table.
+    pub fn flip_kicks(
+        self,
+        table: FlipKicks,
+        from: PieceState,
+        to: PieceState,
+    ) -> ArrayVec<[(i32, i32); 6]> {
+        let (bx, by) = match self {
+            RotationSystem::Ars => {
+                let (fx, fy) = ars_offset(from);
+                let (tx, ty) = ars_offset(to);
+                (tx - fx, ty - fy)
+            }
+            _ => srs_kicks(from, to).next().unwrap(),
+        };
+        let kicks: &[_] = match self {
+            // Neither of these have 180 kicks of their own, so only allow rotating in place.
+            RotationSystem::NoKicks | RotationSystem::Ars => &[(0, 0)],
+            RotationSystem::Srs | RotationSystem::SrsPlus => table.kicks(from.1),
+        };
+        kicks.iter().map(|&(x, y)| (bx + x, by + y)).collect()
+    }
+
+    /// The orientation pieces spawn in.
+    pub fn spawn_orientation(self, piece: Piece) -> RotationState {
+        match (self, piece) {
+            (RotationSystem::Ars, Piece::I) | (RotationSystem::Ars, Piece::O) => {
+                RotationState::North
+            }
+            (RotationSystem::Ars, _) => RotationState::South,
+            _ => RotationState::North,
+        }
+    }
+
+    /// Whether the ARS center column rule applies to this piece. When it does, a kick is only
+    /// attempted if the first obstructed cell of the unkicked rotation (in reading order) is not
+    /// in the center column of the piece's bounding box.
+    pub fn center_column_rule(self, piece: Piece) -> bool {
+        match (self, piece) {
+            (RotationSystem::Ars, Piece::T)
+            | (RotationSystem::Ars, Piece::L)
+            | (RotationSystem::Ars, Piece::J) => true,
+            _ => false,
+        }
+    }
+}
+
+fn srs_kicks(from: PieceState, to: PieceState) -> impl Iterator<Item = (i32, i32)> {
+    let initial_offsets = from.rotation_points();
+    let target_offsets = to.rotation_points();
+    (0..5).map(move |i| {
+        let (x1, y1) = initial_offsets[i];
+        let (x2, y2) = target_offsets[i];
+        (x1 - x2, y1 - y2)
+    })
+}
+
+/// TETR.IO's I piece kicks, relative to the unkicked SRS rotation.
+fn srs_plus_i_kicks(from: RotationState, to: RotationState) -> &'static [(i32, i32); 5] {
+    use RotationState::*;
+    match (from, to) {
+        (North, East) => &[(0, 0), (1, 0), (-2, 0), (-2, -1), (1, 2)],
+        (East, North) => &[(0, 0), (-1, 0), (2, 0), (-1, -2), (2, 1)],
+        (East, South) => &[(0, 0), (-1, 0), (2, 0), (-1, 2), (2, -1)],
+        (South, East) => &[(0, 0), (-2, 0), (1, 0), (-2, 1), (1, -2)],
+        (South, West) => &[(0, 0), (2, 0), (-1, 0), (2, 1), (-1, -2)],
+        (West, South) => &[(0, 0), (1, 0), (-2, 0), (1, 2), (-2, -1)],
+        (West, North) => &[(0, 0), (1, 0), (-2, 0), (1, -2), (-2, 1)],
+        (North, West) => &[(0, 0), (-1, 0), (2, 0), (2, -1), (-1, 2)],
+        _ => &[(0, 0); 5],
+    }
+}
+
+/// Where the SRS rotation point sits within the ARS bounding box. ARS pieces rest against the
+/// bottom of their bounding box, whereas SRS pieces rotate about a fixed center.
+fn ars_offset(state: PieceState) -> (i32, i32) {
+    use Piece::*;
+    use RotationState::*;
+    match (state.0, state.1) {
+        (I, North) => (0, 0),
+        (I, East) => (1, 0),
+        (I, South) => (1, 0),
+        (I, West) => (1, -1),
+
+        (O, North) => (0, 0),
+        (O, East) => (0, 1),
+        (O, South) => (1, 1),
+        (O, West) => (1, 0),
+
+        (S, North) => (0, -1),
+        (S, East) => (-1, 0),
+        (Z, North) => (0, -1),
+        (Z, West) => (1, 0),
+
+        (_, North) => (0, -1),
+        (_, _) => (0, 0),
+    }
+}
+
+/// The kick table used for 180 degree rotations.
+#[derive(Copy, Clone, Debug, Hash, Eq, PartialEq, Serialize, Deserialize)]
+pub enum FlipKicks {
+    Tetrio,
+    Jstris,
+}
+
+impl FlipKicks {
+    fn kicks(self, from: RotationState) -> &'static [(i32, i32)] {
+        use RotationState::*;
+        match (self, from) {
+            (FlipKicks::Tetrio, North) => &[(0, 0), (0, 1), (1, 1), (-1, 1), (1, 0), (-1, 0)],
+            (FlipKicks::Tetrio, East) => &[(0, 0), (1, 0), (1, 2), (1, 1), (0, 2), (0, 1)],
+            (FlipKicks::Tetrio, South) => &[(0, 0), (0, -1), (-1, -1), (1, -1), (-1, 0), (1, 0)],
+            (FlipKicks::Tetrio, West) => &[(0, 0), (-1, 0), (-1, 2), (-1, 1), (0, 2), (0, 1)],
+
+            (FlipKicks::Jstris, North) => &[(0, 0), (0, 1)],
+            (FlipKicks::Jstris, East) => &[(0, 0), (1, 0)],
+            (FlipKicks::Jstris, South) => &[(0, 0), (0, -1)],
+            (FlipKicks::Jstris, West) => &[(0, 0), (-1, 0)],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Board, FallingPiece, PlacementKind, TspinStatus};
+
+    fn piece(kind: Piece, rotation: RotationState, x: i32, y: i32) -> FallingPiece {
+        FallingPiece {
+            kind: PieceState(kind, rotation),
+            x,
+            y,
+            tspin: TspinStatus::None,
+        }
+    }
+
+    const TST: &str = "
+        .#........
+        ..........
+        #.########
+        #..#######
+        #.########
+    ";
+
+    #[test]
+    fn srs_tst_kick() {
+        let mut board: Board = TST.parse().unwrap();
+        let mut t = piece(Piece::T, RotationState::North, 2, 3);
+        assert!(t.cw(&board, RotationSystem::Srs));
+        assert_eq!(
+            t,
+            FallingPiece {
+                tspin: TspinStatus::Full,
+                ..piece(Piece::T, RotationState::East, 1, 1)
+            }
+        );
+        assert_eq!(board.lock_piece(t).placement_kind, PlacementKind::Tspin3);
+    }
+
+    #[test]
+    fn no_kick_failure() {
+        let board: Board = TST.parse().unwrap();
+        let start = piece(Piece::T, RotationState::North, 2, 3);
+        let mut t = start;
+        assert!(!t.cw(&board, RotationSystem::NoKicks));
+        assert_eq!(t, start);
+
+        // rotating in place still works
+        let mut t = piece(Piece::T, RotationState::North, 5, 5);
+        assert!(t.ccw(&board, RotationSystem::NoKicks));
+        assert_eq!(t, piece(Piece::T, RotationState::West, 5, 5));
+    }
+
+    #[test]
+    fn srs_plus_i_kicks_are_symmetric() {
+        use RotationState::*;
+        let mirror = |r| match r {
+            East => West,
+            West => East,
+            r => r,
+        };
+        for &(from, to) in &[
+            (North, East),
+            (East, North),
+            (East, South),
+            (South, East),
+            (South, West),
+            (West, South),
+            (West, North),
+            (North, West),
+        ] {
+            let mirrored: Vec<_> = srs_plus_i_kicks(from, to)
+                .iter()
+                .map(|&(x, y)| (-x, y))
+                .collect();
+            assert_eq!(
+                &srs_plus_i_kicks(mirror(from), mirror(to))[..],
+                &*mirrored,
+                "{:?} -> {:?}",
+                from,
+                to
+            );
+        }
+    }
+
+    #[test]
+    fn srs_plus_only_changes_i_kicks() {
+        let from = PieceState(Piece::T, RotationState::North);
+        let to = PieceState(Piece::T, RotationState::East);
+        assert_eq!(
+            RotationSystem::SrsPlus.kicks(from, to),
+            RotationSystem::Srs.kicks(from, to)
+        );
+        let from = PieceState(Piece::I, RotationState::North);
+        let to = PieceState(Piece::I, RotationState::East);
+        assert_ne!(
+            RotationSystem::SrsPlus.kicks(from, to),
+            RotationSystem::Srs.kicks(from, to)
+        );
+    }
+
+    #[test]
+    fn ars_center_column() {
+        // The unkicked rotation is blocked in the center column, so no kick is tried even
+        // though kicking right would fit.
+        let board: Board = "
+            ....#.....
+            ..........
+            ..........
+        "
+        .parse()
+        .unwrap();
+        let start = piece(Piece::T, RotationState::South, 4, 1);
+        let mut t = start;
+        assert!(!t.cw(&board, RotationSystem::Ars));
+        assert_eq!(t, start);
+        assert!(!board.obstructed(&piece(Piece::T, RotationState::West, 5, 1)));
+
+        // Blocked outside the center column, so the piece kicks. Right is tried before left.
+        let board: Board = ".....#....".parse().unwrap();
+        let mut t = piece(Piece::T, RotationState::West, 4, 1);
+        assert!(t.cw(&board, RotationSystem::Ars));
+        assert_eq!(t, piece(Piece::T, RotationState::North, 3, 0));
+    }
+}
@@ -0,0 +1,310 @@
+//! A plain text format for boards, meant for tests and bug reports.
+//!
+//! A board is written as optional header lines followed by the rows of the field, top row first.
+//! Rows above the ones given are empty.
+//!
+//! ```plain
+//! hold: T
+//! queue: IOZ
+//! bag: LJS
+//! combo: 2
+//! b2b: 1
+//! ##..SS....
+//! #..SS..###
+//! ```
+//!
+//! Cells are `.` for empty, `#` for garbage, `X` for unclearable cells, or a piece letter for the
+//! color of that piece. Boards that don't track colors show every filled cell as `#`. `bag` lists
+//! the pieces remaining in the 7-bag after the queue, and `b2b` is the length of the back-to-back
+//! chain. Boards that aren't 10x40 have `width` and `height` headers.
+
+use std::fmt;
+use std::str::FromStr;
+
+use enumset::EnumSet;
+
+use crate::*;
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ParseBoardError {
+    /// The line the error occurred on, starting at 1.
+    pub line: usize,
+    pub reason: &'static str,
+}
+
+impl fmt::Display for ParseBoardError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.reason)
+    }
+}
+
+impl std::error::Error for ParseBoardError {}
+
+impl<R: Row> FromStr for Board<R> {
+    type Err = ParseBoardError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut hold = None;
+        let mut combo = 0;
+        let mut b2b = 0;
+        let mut width = 10;
+        let mut height = MAX_HEIGHT;
+        let mut queue = vec![];
+        let mut bag = None;
+        let mut rows = vec![];
+
+        for (i, line) in s.lines().enumerate() {
+            let error = |reason| ParseBoardError {
+                line: i + 1,
+                reason,
+            };
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            if let Some(colon) = line.find(':') {
+                if !rows.is_empty() {
+                    return Err(error("header after field rows"));
+                }
+                let value = line[colon + 1..].trim();
+                let pieces = || {
+                    value
+                        .chars()
+                        .map(Piece::from_char)
+                        .collect::<Option<Vec<_>>>()
+                        .ok_or_else(|| error("invalid piece"))
+                };
+                match line[..colon].trim() {
+                    "hold" => match &*pieces()? {
+                        [] => hold = None,
+                        &[p] => hold = Some(p),
+                        _ => return Err(error("more than one hold piece")),
+                    },
+                    "queue" => queue = pieces()?,
+                    "bag" => bag = Some(pieces()?.into_iter().collect::<EnumSet<_>>()),
+                    "combo" => combo = value.parse().map_err(|_| error("invalid combo"))?,
+                    "b2b" => b2b = value.parse().map_err(|_| error("invalid b2b"))?,
+                    "width" => {
+                        width = value
+                            .parse()
+                            .ok()
+                            .filter(|w| (4..=MAX_WIDTH).contains(w))
+                            .ok_or_else(|| error("invalid width"))?
+                    }
+                    "height" => {
+                        height = value
+                            .parse()
+                            .ok()
+                            .filter(|h| (4..=MAX_HEIGHT).contains(h))
+                            .ok_or_else(|| error("invalid height"))?
+                    }
+                    _ => return Err(error("unknown header")),
+                }
+                continue;
+            }
+
+            let mut row = [CellColor::Empty; MAX_WIDTH];
+            if line.chars().count() != width {
+                return Err(error("row is not the width of the board"));
+            }
+            for (cell, c) in row.iter_mut().zip(line.chars()) {
+                *cell = match c {
+                    '.' => CellColor::Empty,
+                    '#' => CellColor::Garbage,
+                    'X' => CellColor::Unclearable,
+                    c => Piece::from_char(c)
+                        .ok_or_else(|| error("invalid cell"))?
+                        .color(),
+                };
+            }
+            rows.push(row);
+            if rows.len() > height {
+                return Err(error("more rows than the board height"));
+            }
+        }
+
+        let mut board = Board::with_size(width, height);
+        board.hold_piece = hold;
+        board.combo = combo;
+        board.b2b_chain = b2b;
+
+        for (y, row) in rows.iter().rev().enumerate() {
+            for (x, &color) in row.iter().enumerate() {
+                if color != CellColor::Empty {
+                    board.set_cell_color(x as i32, y as i32, color);
+                }
+            }
+        }
+        for p in queue {
+            board.add_next_piece(p);
+        }
+        if let Some(bag) = bag {
+            board.randomizer = SevenBag {
+                bag: if bag.is_empty() { EnumSet::all() } else { bag },
+            }
+            .into();
+        }
+        Ok(board)
+    }
+}
+
+impl<R: Row> fmt::Display for Board<R> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let Some(hold) = self.hold_piece {
+            writeln!(f, "hold: {}", hold.to_char())?;
+        }
+        if self.next_queue().next().is_some() {
+            let queue: String = self.next_queue().map(Piece::to_char).collect();
+            writeln!(f, "queue: {}", queue)?;
+        }
+        if let PieceRandomizer::SevenBag(r) = self.randomizer {
+            if r.bag != EnumSet::all() {
+                let bag: String = r.bag.iter().map(Piece::to_char).collect();
+                writeln!(f, "bag: {}", bag)?;
+            }
+        }
+        if self.combo != 0 {
+            writeln!(f, "combo: {}", self.combo)?;
+        }
+        if self.b2b_chain != 0 {
+            writeln!(f, "b2b: {}", self.b2b_chain)?;
+        }
+        if self.width() != 10 || self.height() != MAX_HEIGHT as i32 {
+            writeln!(f, "width: {}", self.width())?;
+            writeln!(f, "height: {}", self.height())?;
+        }
+        let height = self.column_heights().iter().copied().max().unwrap();
+        for y in (0..height).rev() {
+            let row: String = (0..self.width() as usize)
+                .map(|x| match self.get_row(y).cell_color(x) {
+                    CellColor::Empty => '.',
+                    CellColor::Garbage => '#',
+                    CellColor::Unclearable => 'X',
+                    CellColor::I => 'I',
+                    CellColor::O => 'O',
+                    CellColor::T => 'T',
+                    CellColor::L => 'L',
+                    CellColor::J => 'J',
+                    CellColor::S => 'S',
+                    CellColor::Z => 'Z',
+                })
+                .collect();
+            writeln!(f, "{}", row)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const COLORED: &str = "\
+hold: T
+queue: IOZ
+bag: LJS
+combo: 2
+b2b: 3
+....X.....
+##..SS...I
+#TSS..OO#I
+";
+
+    fn queue<R: Row>(board: &Board<R>) -> Vec<Piece> {
+        board.next_queue().collect()
+    }
+
+    fn check_headers<R: Row>(board: &Board<R>) {
+        assert_eq!(board.hold_piece, Some(Piece::T));
+        assert_eq!(queue(board), [Piece::I, Piece::O, Piece::Z]);
+        assert_eq!(
+            board.randomizer,
+            SevenBag {
+                bag: Piece::L | Piece::J | Piece::S
+            }
+            .into()
+        );
+        assert_eq!(board.combo, 2);
+        assert_eq!(board.b2b_chain, 3);
+        assert_eq!(board.column_heights(), &[2, 2, 1, 1, 3, 2, 1, 1, 1, 2]);
+    }
+
+    #[test]
+    fn colored_round_trip() {
+        let board: Board<ColoredRow> = COLORED.parse().unwrap();
+        check_headers(&board);
+        assert_eq!(board.get_row(0).cell_color(1), CellColor::T);
+        assert_eq!(board.get_row(1).cell_color(0), CellColor::Garbage);
+        assert_eq!(board.get_row(2).cell_color(4), CellColor::Unclearable);
+        assert_eq!(board.to_string(), COLORED);
+    }
+
+    #[test]
+    fn uncolored_round_trip() {
+        let board: Board = COLORED.parse().unwrap();
+        check_headers(&board);
+        let uncolored = COLORED
+            .lines()
+            .map(|line| match line.contains(':') {
+                true => line.to_owned(),
+                false => line.replace(|c| c != '.', "#"),
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+            + "\n";
+        assert_eq!(board.to_string(), uncolored);
+
+        let reparsed: Board = uncolored.parse().unwrap();
+        check_headers(&reparsed);
+        assert_eq!(reparsed.zobrist_hash(), board.zobrist_hash());
+        assert_eq!(reparsed.to_string(), uncolored);
+    }
+
+    #[test]
+    fn headers_are_optional() {
+        let board: Board = "".parse().unwrap();
+        assert_eq!(board.to_string(), "");
+        assert_eq!(board.zobrist_hash(), Board::<u16>::new().zobrist_hash());
+
+        let board: Board = "
+            queue: S
+            #.........
+        "
+        .parse()
+        .unwrap();
+        assert_eq!(board.hold_piece, None);
+        assert_eq!(queue(&board), [Piece::S]);
+        // the queue is dealt from a new bag
+        assert_eq!(board.next_bag(), EnumSet::all());
+        assert_eq!(board.to_string(), "queue: S\nbag: IOTLJZ\n#.........\n");
+    }
+
+    #[test]
+    fn empty_bag_means_a_new_bag() {
+        let board: Board = "bag:".parse().unwrap();
+        assert_eq!(board.randomizer, SevenBag::new().into());
+    }
+
+    #[test]
+    fn sized_round_trip() {
+        let text = "width: 6\nheight: 12\n#.LL..\n#.L.##\n";
+        let board: Board<ColoredRow> = text.parse().unwrap();
+        assert_eq!(board.width(), 6);
+        assert_eq!(board.height(), 12);
+        assert_eq!(board.to_string(), text);
+    }
+
+    #[test]
+    fn errors() {
+        let error = |text: &str| text.parse::<Board>().unwrap_err();
+        assert_eq!(error("hold: TI").reason, "more than one hold piece");
+        assert_eq!(error("queue: IQ").reason, "invalid piece");
+        assert_eq!(error("combo: -1").reason, "invalid combo");
+        assert_eq!(error("speed: 20").reason, "unknown header");
+        assert_eq!(error("#.........\nhold: T").line, 2);
+        assert_eq!(error("\n#........").line, 2);
+        assert_eq!(error("#........?").reason, "invalid cell");
+        assert_eq!(error("width: 3").reason, "invalid width");
+    }
+}
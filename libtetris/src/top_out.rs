@@ -0,0 +1,70 @@
+use serde::{Deserialize, Serialize};
+
+/// Describes the ways a player can top out.
+#[derive(Copy, Clone, Debug, Hash, Eq, PartialEq, Serialize, Deserialize)]
+pub struct TopOutRules {
+    /// Lock out and garbage top out are measured against the rows below this one.
+    pub visible_rows: i32,
+    pub lock_out: LockOut,
+    pub block_out: BlockOut,
+    /// Garbage that pushes cells off the top of the board tops out.
+    pub garbage_push_out: bool,
+    /// Garbage that leaves any cell at or above `visible_rows` tops out.
+    pub garbage_top_out: bool,
+}
+
+#[derive(Copy, Clone, Debug, Hash, Eq, PartialEq, Serialize, Deserialize)]
+pub enum LockOut {
+    /// Locking a piece entirely outside the visible rows tops out.
+    Complete,
+    /// Locking a piece with any cell outside the visible rows tops out.
+    Partial,
+    /// Pieces can lock anywhere.
+    Disabled,
+}
+
+#[derive(Copy, Clone, Debug, Hash, Eq, PartialEq, Serialize, Deserialize)]
+pub enum BlockOut {
+    /// A piece that doesn't fit where it spawns tops out.
+    Overlap,
+    /// A piece that doesn't fit where it spawns is moved up until it fits, topping out only if
+    /// it reaches the top of the board.
+    Raise,
+}
+
+impl TopOutRules {
+    /// The guideline rules.
+    pub fn guideline() -> Self {
+        TopOutRules {
+            visible_rows: 20,
+            lock_out: LockOut::Complete,
+            block_out: BlockOut::Overlap,
+            garbage_push_out: true,
+            garbage_top_out: false,
+        }
+    }
+
+    /// TETR.IO, which also tops out if garbage leaves any cell above row 20.
+    pub fn tetrio() -> Self {
+        TopOutRules {
+            garbage_top_out: true,
+            ..TopOutRules::guideline()
+        }
+    }
+
+    /// Whether locking a piece with cells in these rows tops out.
+    pub fn locked_out(&self, rows: impl IntoIterator<Item = i32>) -> bool {
+        let mut rows = rows.into_iter();
+        match self.lock_out {
+            LockOut::Complete => rows.all(|y| y >= self.visible_rows),
+            LockOut::Partial => rows.any(|y| y >= self.visible_rows),
+            LockOut::Disabled => false,
+        }
+    }
+}
+
+impl Default for TopOutRules {
+    fn default() -> Self {
+        TopOutRules::guideline()
+    }
+}
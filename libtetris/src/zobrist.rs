@@ -0,0 +1,46 @@
+//! Keys for the Zobrist hash of a board. Keys are generated on demand with the splitmix64 mixing
+//! function instead of being stored in tables.
+
+use crate::{Piece, MAX_WIDTH};
+
+const CELL: u64 = 0;
+const HOLD: u64 = 1 << 32;
+const RESERVE: u64 = 2 << 32;
+const BAG: u64 = 3 << 32;
+const COMBO: u64 = 4 << 32;
+const B2B: u64 = 5 << 32;
+
+fn splitmix64(x: u64) -> u64 {
+    let mut z = x.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+fn piece_index(piece: Option<Piece>) -> u64 {
+    piece.map_or(0, |p| p as u64 + 1)
+}
+
+pub(crate) fn cell(x: usize, y: usize) -> u64 {
+    splitmix64(CELL + (y * MAX_WIDTH + x) as u64)
+}
+
+pub(crate) fn hold(piece: Option<Piece>) -> u64 {
+    splitmix64(HOLD + piece_index(piece))
+}
+
+pub(crate) fn reserve(piece: Option<Piece>) -> u64 {
+    splitmix64(RESERVE + piece_index(piece))
+}
+
+pub(crate) fn bag(piece: Piece) -> u64 {
+    splitmix64(BAG + piece as u64)
+}
+
+pub(crate) fn combo(combo: u32) -> u64 {
+    splitmix64(COMBO + combo as u64)
+}
+
+pub(crate) fn b2b(chain: u32) -> u64 {
+    splitmix64(B2B + chain as u64)
+}
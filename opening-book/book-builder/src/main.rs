@@ -34,7 +34,7 @@ fn main() {
 
         let mut b = Board::new();
         b.set_field(field);
-        b.bag = enumset::EnumSet::empty();
+        let mut bag = enumset::EnumSet::empty();
         for c in bagspec.chars() {
             let p = match c.to_ascii_uppercase() {
                 'S' => Piece::S,
@@ -46,16 +46,17 @@ fn main() {
                 'J' => Piece::J,
                 _ => continue,
             };
-            if b.bag.contains(p) {
+            if bag.contains(p) {
                 b.hold_piece = Some(p);
             } else {
-                b.bag |= p;
+                bag |= p;
             }
         }
-        if b.hold_piece.is_none() && b.bag.len() <= 1 {
-            b.hold_piece = b.bag.iter().next();
-            b.bag = enumset::EnumSet::all();
+        if b.hold_piece.is_none() && bag.len() <= 1 {
+            b.hold_piece = bag.iter().next();
+            bag = enumset::EnumSet::all();
         }
+        b.randomizer = SevenBag { bag }.into();
 
         if fumen.pages.len() == 1 {
             match value {
@@ -169,7 +170,9 @@ fn convert(p: fumen::Piece) -> FallingPiece {
 
 fn mirror_board(b: &Board) -> Board {
     let mut b = b.clone();
-    b.bag = b.bag.iter().map(mirror_piece).collect();
+    if let PieceRandomizer::SevenBag(r) = &mut b.randomizer {
+        r.bag = r.bag.iter().map(mirror_piece).collect();
+    }
     b.hold_piece = b.hold_piece.map(mirror_piece);
     let mut f = b.get_field();
     for r in &mut f[..] {
@@ -340,7 +343,7 @@ fn add_pcs(book: &mut BookBuilder, b: &Board, pieces: u32) {
     );
     let mut count = 0;
     let mut pieceset = pcf::PieceSet::default();
-    for p in b.bag {
+    for p in b.randomizer.possible() {
         pieceset = pieceset.with(p.into());
         count += 1;
     }
@@ -372,7 +375,7 @@ fn add_pcs(book: &mut BookBuilder, b: &Board, pieces: u32) {
 
     let book = &std::sync::Mutex::new(book);
     rayon::scope(|s| {
-        all_sequences(b.bag, pieces as usize, |q| {
+        all_sequences(b.randomizer.possible(), pieces as usize, |q| {
             s.spawn(move |_| {
                 let set: pcf::PieceSet = q.iter().copied().collect();
                 for combo in combinations.get(&set).map(|v| &**v).unwrap_or(&[]) {
@@ -304,3 +304,102 @@ impl From<DiskBook> for Book {
         Book(BookType::Disk(v))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use libtetris::{PieceState, RotationState, TspinStatus};
+
+    fn board(text: &str) -> Board {
+        text.parse().unwrap()
+    }
+
+    fn seq(next: EnumSet<Piece>, queue: [Piece; NEXT_PIECES]) -> Sequence {
+        Sequence { next, queue }
+    }
+
+    fn piece(kind: Piece, x: i32, y: i32) -> FallingPiece {
+        FallingPiece {
+            kind: PieceState(kind, RotationState::North),
+            x,
+            y,
+            tspin: TspinStatus::None,
+        }
+    }
+
+    const START: &str = "
+        hold: T
+        queue: IOZLJ
+        bag: S
+        ######....
+    ";
+
+    #[test]
+    fn decompose() {
+        let (pos, seq) = decompose_board(&board(START)).unwrap();
+        assert_eq!(pos.rows()[0], 0b111111);
+        assert_eq!(pos.bag(), EnumSet::all());
+        assert_eq!(pos.extra(), None);
+        assert_eq!(seq.next, Piece::I | Piece::T);
+        assert_eq!(seq.queue, [Piece::O, Piece::Z, Piece::L, Piece::J]);
+
+        // not enough pieces in the queue to be in the book
+        assert!(decompose_board(&board("hold: T\nqueue: IOZL")).is_none());
+    }
+
+    #[test]
+    fn advance() {
+        let (after, _) = Position::from(&board(START)).advance(piece(Piece::I, 7, 0));
+        let expected = board(
+            "
+            hold: T
+            queue: OZLJ
+            bag: S
+        ",
+        );
+        assert_eq!(after, Position::from(&expected));
+    }
+
+    #[test]
+    fn memory_book_lookup() {
+        let start = board(START);
+        let (pos, first) = decompose_board(&start).unwrap();
+        let later = seq(Piece::O | Piece::T, [Piece::Z; NEXT_PIECES]);
+        let tetris = piece(Piece::I, 7, 0);
+        let mut book = MemoryBook(HashMap::new());
+        book.0.insert(
+            pos,
+            Row(vec![(first, Some(tetris.into())), (later, None)].into_boxed_slice()),
+        );
+
+        assert_eq!(book.suggest_move(&start), Some(tetris));
+        // sequences between entries use the preceding entry
+        let between = board("hold: T\nqueue: ISZLJ\nbag: O\n######....");
+        assert_eq!(book.suggest_move(&between), Some(tetris));
+        let after = board("hold: T\nqueue: OZZZZ\n######....");
+        assert_eq!(book.suggest_move(&after), None);
+        assert_eq!(book.suggest_move(&board("queue: IOZLJS")), None);
+    }
+
+    #[test]
+    fn row_round_trip() {
+        let row = Row(vec![
+            (seq(Piece::I | Piece::T, [Piece::O; NEXT_PIECES]), None),
+            (
+                seq(Piece::S.into(), [Piece::Z, Piece::L, Piece::J, Piece::I]),
+                Some(piece(Piece::T, 4, 2).into()),
+            ),
+        ]
+        .into_boxed_slice());
+        let mut bytes = vec![];
+        row.custom_serialize(&mut bytes).unwrap();
+        let read = Row::custom_deserialize(bytes.as_slice()).unwrap();
+        assert_eq!(read.0.len(), 2);
+        for (a, b) in read.0.iter().zip(&*row.0) {
+            assert_eq!(a.0, b.0);
+            assert_eq!(a.1.map(FallingPiece::from), b.1.map(FallingPiece::from));
+        }
+
+        assert!(Row::custom_deserialize(&[0, 7, 0, 0, 0, 0, 0][..]).is_err());
+    }
+}
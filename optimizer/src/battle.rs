@@ -20,7 +20,7 @@ impl<E: Evaluator> BotInput<E> {
         let mut this = BotInput {
             controller: Controller::default(),
             executing: None,
-            bot: cold_clear::BotState::new(board, Default::default(),is_bot_new),
+            bot: cold_clear::BotState::new(board, Default::default()),
             eval,
         };
         for _ in 0..180 {
@@ -70,7 +70,7 @@ impl<E: Evaluator> BotInput<E> {
                 }
                 Event::GarbageAdded(_) => {
                     self.bot
-                        .reset(board.get_field(), board.b2b_bonus, board.combo);
+                        .reset(board.get_field(), board.b2b_bonus(), board.combo);
                 }
                 _ => {}
             }
@@ -80,7 +80,7 @@ impl<E: Evaluator> BotInput<E> {
             if let Some(loc) = executor.update(&mut self.controller, board, events) {
                 if loc != expected {
                     self.bot
-                        .reset(board.get_field(), board.b2b_bonus, board.combo);
+                        .reset(board.get_field(), board.b2b_bonus(), board.combo);
                 }
                 self.executing = None;
             }
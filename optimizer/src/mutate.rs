@@ -13,6 +13,7 @@ impl Mutateable for Standard {
     fn generate(sub_name: String) -> Self {
         Standard {
             back_to_back: thread_rng().gen_range(-999, 1000),
+            b2b_chain: thread_rng().gen_range(-999, 1000),
             bumpiness: thread_rng().gen_range(-999, 1000),
             bumpiness_sq: thread_rng().gen_range(-999, 1000),
             row_transitions: thread_rng().gen_range(-999, 1000),
@@ -50,6 +51,7 @@ impl Mutateable for Standard {
             move_time: thread_rng().gen_range(-999, 1000),
             wasted_t: thread_rng().gen_range(-999, 1000),
             b2b_clear: thread_rng().gen_range(-999, 1000),
+            b2b_surge: thread_rng().gen_range(-999, 1000),
             clear1: thread_rng().gen_range(-999, 1000),
             clear2: thread_rng().gen_range(-999, 1000),
             clear3: thread_rng().gen_range(-999, 1000),
@@ -59,6 +61,13 @@ impl Mutateable for Standard {
             tspin3: thread_rng().gen_range(-999, 1000),
             mini_tspin1: thread_rng().gen_range(-999, 1000),
             mini_tspin2: thread_rng().gen_range(-999, 1000),
+            mini_tspin3: thread_rng().gen_range(-999, 1000),
+            mini_spin1: thread_rng().gen_range(-999, 1000),
+            mini_spin2: thread_rng().gen_range(-999, 1000),
+            mini_spin3: thread_rng().gen_range(-999, 1000),
+            spin1: thread_rng().gen_range(-999, 1000),
+            spin2: thread_rng().gen_range(-999, 1000),
+            spin3: thread_rng().gen_range(-999, 1000),
             perfect_clear: thread_rng().gen_range(-999, 1000),
             combo_garbage: thread_rng().gen_range(-999, 1000),
 
@@ -72,6 +81,7 @@ impl Mutateable for Standard {
     fn crossover(parent1: &Self, parent2: &Self, sub_name: String) -> Self {
         Standard {
             back_to_back: crossover_gene(parent1.back_to_back, parent2.back_to_back),
+            b2b_chain: crossover_gene(parent1.b2b_chain, parent2.b2b_chain),
             bumpiness: crossover_gene(parent1.bumpiness, parent2.bumpiness),
             bumpiness_sq: crossover_gene(parent1.bumpiness_sq, parent2.bumpiness_sq),
             row_transitions: crossover_gene(parent1.row_transitions, parent2.row_transitions),
@@ -109,6 +119,7 @@ impl Mutateable for Standard {
             move_time: crossover_gene(parent1.move_time, parent2.move_time),
             wasted_t: crossover_gene(parent1.wasted_t, parent2.wasted_t),
             b2b_clear: crossover_gene(parent1.b2b_clear, parent2.b2b_clear),
+            b2b_surge: crossover_gene(parent1.b2b_surge, parent2.b2b_surge),
             clear1: crossover_gene(parent1.clear1, parent2.clear1),
             clear2: crossover_gene(parent1.clear2, parent2.clear2),
             clear3: crossover_gene(parent1.clear3, parent2.clear3),
@@ -118,6 +129,13 @@ impl Mutateable for Standard {
             tspin3: crossover_gene(parent1.tspin3, parent2.tspin3),
             mini_tspin1: crossover_gene(parent1.mini_tspin1, parent2.mini_tspin1),
             mini_tspin2: crossover_gene(parent1.mini_tspin2, parent2.mini_tspin2),
+            mini_tspin3: crossover_gene(parent1.mini_tspin3, parent2.mini_tspin3),
+            mini_spin1: crossover_gene(parent1.mini_spin1, parent2.mini_spin1),
+            mini_spin2: crossover_gene(parent1.mini_spin2, parent2.mini_spin2),
+            mini_spin3: crossover_gene(parent1.mini_spin3, parent2.mini_spin3),
+            spin1: crossover_gene(parent1.spin1, parent2.spin1),
+            spin2: crossover_gene(parent1.spin2, parent2.spin2),
+            spin3: crossover_gene(parent1.spin3, parent2.spin3),
             perfect_clear: crossover_gene(parent1.perfect_clear, parent2.perfect_clear),
             combo_garbage: crossover_gene(parent1.combo_garbage, parent2.combo_garbage),
 
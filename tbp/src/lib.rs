@@ -39,10 +39,13 @@ pub async fn run(
                     b.add_next_piece(from_tbp_piece(piece));
                 }
                 if let RandomizerState::SevenBag { bag_state } = &randomizer {
-                    b.bag = bag_state.iter().copied().map(from_tbp_piece).collect();
+                    b.randomizer = libtetris::SevenBag {
+                        bag: bag_state.iter().copied().map(from_tbp_piece).collect(),
+                    }
+                    .into();
                 }
                 b.combo = combo;
-                b.b2b_bonus = back_to_back;
+                b.set_b2b_bonus(back_to_back);
                 let mut field = [[false; 10]; 40];
                 for y in 0..40 {
                     for x in 0..10 {
@@ -71,7 +74,7 @@ pub async fn run(
             }
             FrontendMessage::Suggest => {
                 if let Some(ref mut bot) = bot {
-                    bot.suggest_next_move(0);
+                    bot.suggest_next_move(0, None);
                     #[cfg(not(target_arch = "wasm32"))]
                     let mvs = bot.block_next_move();
                     #[cfg(target_arch = "wasm32")]